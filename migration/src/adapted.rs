@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+use crate::m20240805_064539_add_username;
+
+/// `add_username` as it shipped, with a statement MySQL accepts
+///
+/// Its name is unchanged, so databases that applied it are not affected. MySQL does not accept the
+/// `ADD COLUMN IF NOT EXISTS` it uses, the column is added without the check there.
+pub struct AddUsername(pub m20240805_064539_add_username::Migration);
+
+impl MigrationName for AddUsername {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for AddUsername {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DatabaseBackend::MySql {
+            return self.0.up(manager).await;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::Username).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        self.0.down(manager).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Username,
+}
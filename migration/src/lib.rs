@@ -1,9 +1,20 @@
 pub use sea_orm_migration::prelude::*;
 
+use crate::adapted::AddUsername;
+
+mod adapted;
 mod m20240708_142859_create_user_table;
 mod m20240708_155447_create_profile_table;
 mod m20240709_091713_create_texture_table;
 mod m20240805_064539_add_username;
+mod m20240820_103012_create_token_table;
+mod m20240826_142230_add_token_profile;
+mod m20240903_091544_create_invite_table;
+mod m20240910_083127_add_user_verified;
+mod m20240915_120418_widen_user_password;
+mod m20240922_091205_add_relations;
+mod m20240928_140236_add_texture_metadata;
+mod m20241005_093412_add_user_unique_indexes;
 
 pub struct Migrator;
 
@@ -14,7 +25,40 @@ impl MigratorTrait for Migrator {
             Box::new(m20240708_142859_create_user_table::Migration),
             Box::new(m20240708_155447_create_profile_table::Migration),
             Box::new(m20240709_091713_create_texture_table::Migration),
-            Box::new(m20240805_064539_add_username::Migration),
+            Box::new(AddUsername(m20240805_064539_add_username::Migration)),
+            Box::new(m20240820_103012_create_token_table::Migration),
+            Box::new(m20240826_142230_add_token_profile::Migration),
+            Box::new(m20240903_091544_create_invite_table::Migration),
+            Box::new(m20240910_083127_add_user_verified::Migration),
+            Box::new(m20240915_120418_widen_user_password::Migration),
+            Box::new(m20240922_091205_add_relations::Migration),
+            Box::new(m20240928_140236_add_texture_metadata::Migration),
+            Box::new(m20241005_093412_add_user_unique_indexes::Migration),
         ]
     }
-}
\ No newline at end of file
+}
+
+/// Apply every migration then roll them all back, against `TEST_DATABASE_URL` if set,
+/// otherwise against a temporary SQLite database when the `sqlite` feature is enabled
+///
+/// The readme lists the command for each database, CI runs all of them.
+#[tokio::test]
+async fn test_migrations() {
+    let url = match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) => url,
+        Err(_) if cfg!(feature = "sqlite") => {
+            let path = std::env::temp_dir().join(format!("migration-test-{}.db", std::process::id()));
+            format!("sqlite://{}?mode=rwc", path.display())
+        }
+        Err(_) => {
+            eprintln!("test_migrations skipped: set TEST_DATABASE_URL or enable the sqlite feature");
+            return;
+        }
+    };
+    let db = sea_orm_migration::sea_orm::Database::connect(&url).await.unwrap();
+
+    Migrator::up(&db, None).await.unwrap();
+    assert!(Migrator::get_pending_migrations(&db).await.unwrap().is_empty());
+    Migrator::down(&db, None).await.unwrap();
+    assert_eq!(Migrator::get_applied_migrations(&db).await.unwrap().len(), 0);
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Token::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Token::AccessToken)
+                            .string_len(32)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Token::ClientToken).string().not_null())
+                    .col(ColumnDef::new(Token::UserId).string_len(32).not_null())
+                    .col(ColumnDef::new(Token::IssuedTime).timestamp().default(Expr::current_timestamp()).not_null())
+                    .col(ColumnDef::new(Token::Available).boolean().default(true).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_token_user_id")
+                    .table(Token::Table)
+                    .col(Token::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Token::Table).to_owned())
+            .await
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(DeriveIden)]
+enum Token {
+    Table,
+    AccessToken,
+    ClientToken,
+    UserId,
+    IssuedTime,
+    Available,
+}
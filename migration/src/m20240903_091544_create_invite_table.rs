@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invite::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invite::Code)
+                            .string_len(32)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Invite::CreatorId).string_len(32).null())
+                    .col(ColumnDef::new(Invite::ProfileName).string().null())
+                    .col(ColumnDef::new(Invite::MaxUses).integer().default(1).not_null())
+                    .col(ColumnDef::new(Invite::UsedCount).integer().default(0).not_null())
+                    .col(ColumnDef::new(Invite::ExpireTime).timestamp().null())
+                    .col(ColumnDef::new(Invite::CreateTime).timestamp().default(Expr::current_timestamp()).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::InviteCode).string_len(32).null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::InviteCode)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Invite::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Invite {
+    Table,
+    Code,
+    CreatorId,
+    ProfileName,
+    MaxUses,
+    UsedCount,
+    ExpireTime,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    InviteCode,
+}
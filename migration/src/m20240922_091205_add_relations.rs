@@ -0,0 +1,306 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        check_orphan_profiles(manager).await?;
+
+        // `user.profile_id` used an empty string for users without profile, it becomes nullable
+        if backend == DatabaseBackend::Sqlite {
+            rebuild_sqlite_user_table(manager).await?;
+        } else {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .modify_column(ColumnDef::new(User::ProfileId).string_len(32).null())
+                        .to_owned(),
+                )
+                .await?;
+            let clear_missing = Query::update()
+                .table(User::Table)
+                .value(User::ProfileId, Option::<String>::None)
+                .and_where(Expr::col(User::ProfileId).not_in_subquery(
+                    Query::select().column(Profile::Id).from(Profile::Table).to_owned(),
+                ))
+                .to_owned();
+            db.execute(backend.build(&clear_missing)).await?;
+        }
+
+        // Textures used to be referenced without a row in `texture`
+        for (column, model) in [(Profile::SkinTexture, "default"), (Profile::CapeTexture, "cape")] {
+            let missing = Query::select()
+                .distinct()
+                .column(column.clone())
+                .expr(Expr::val(model))
+                .from(Profile::Table)
+                .and_where(Expr::col(column.clone()).is_not_null())
+                .and_where(Expr::col(column).not_in_subquery(
+                    Query::select().column(Texture::Id).from(Texture::Table).to_owned(),
+                ))
+                .to_owned();
+            let mut insert = Query::insert();
+            insert
+                .into_table(Texture::Table)
+                .columns([Texture::Id, Texture::Model])
+                .select_from(missing)
+                .map_err(|e| DbErr::Migration(e.to_string()))?;
+            db.execute(backend.build(&insert)).await?;
+        }
+
+        check_duplicate_names(manager).await?;
+        if backend == DatabaseBackend::MySql {
+            // The default collation of MySQL already ignores case
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_profile_name_lower")
+                        .table(Profile::Table)
+                        .col(Profile::Name)
+                        .unique()
+                        .to_owned(),
+                )
+                .await?;
+        } else {
+            db.execute_unprepared("CREATE UNIQUE INDEX idx_profile_name_lower ON profile (LOWER(name))")
+                .await?;
+        }
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_profile_owner_id")
+                    .table(Profile::Table)
+                    .col(Profile::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite can not add foreign keys to existing tables, only the rebuilt user table declares its own.
+        // The server deletes the profiles of a user itself and only deletes textures no profile uses.
+        if backend == DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+        for foreign_key in foreign_keys() {
+            manager.create_foreign_key(foreign_key).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        if backend != DatabaseBackend::Sqlite {
+            manager
+                .drop_foreign_key(ForeignKey::drop().name("fk_user_profile_id").table(User::Table).to_owned())
+                .await?;
+            for name in ["fk_profile_owner_id", "fk_profile_skin_texture", "fk_profile_cape_texture"] {
+                manager
+                    .drop_foreign_key(ForeignKey::drop().name(name).table(Profile::Table).to_owned())
+                    .await?;
+            }
+        }
+        for name in ["idx_profile_owner_id", "idx_profile_name_lower"] {
+            manager
+                .drop_index(Index::drop().name(name).table(Profile::Table).to_owned())
+                .await?;
+        }
+
+        // The nullable column is kept on SQLite, the table would have to be rebuilt again
+        if backend != DatabaseBackend::Sqlite {
+            let fill_empty = Query::update()
+                .table(User::Table)
+                .value(User::ProfileId, "")
+                .and_where(Expr::col(User::ProfileId).is_null())
+                .to_owned();
+            db.execute(backend.build(&fill_empty)).await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .modify_column(ColumnDef::new(User::ProfileId).string().not_null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn foreign_keys() -> Vec<ForeignKeyCreateStatement> {
+    vec![
+        ForeignKey::create()
+            .name("fk_profile_owner_id")
+            .from(Profile::Table, Profile::OwnerId)
+            .to(User::Table, User::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction)
+            .to_owned(),
+        ForeignKey::create()
+            .name("fk_user_profile_id")
+            .from(User::Table, User::ProfileId)
+            .to(Profile::Table, Profile::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::NoAction)
+            .to_owned(),
+        ForeignKey::create()
+            .name("fk_profile_skin_texture")
+            .from(Profile::Table, Profile::SkinTexture)
+            .to(Texture::Table, Texture::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::NoAction)
+            .to_owned(),
+        ForeignKey::create()
+            .name("fk_profile_cape_texture")
+            .from(Profile::Table, Profile::CapeTexture)
+            .to(Texture::Table, Texture::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::NoAction)
+            .to_owned(),
+    ]
+}
+
+/// Fail with the profiles whose owner is gone, they have to be deleted or given to a user by hand
+async fn check_orphan_profiles(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let orphans = Query::select()
+        .column(Profile::Name)
+        .from(Profile::Table)
+        .and_where(Expr::col(Profile::OwnerId).not_in_subquery(
+            Query::select().column(User::Id).from(User::Table).to_owned(),
+        ))
+        .to_owned();
+    let names = db
+        .query_all(manager.get_database_backend().build(&orphans))
+        .await?
+        .iter()
+        .map(|row| row.try_get_by_index::<String>(0))
+        .collect::<Result<Vec<String>, DbErr>>()?;
+
+    if !names.is_empty() {
+        return Err(DbErr::Migration(format!(
+            "Profiles whose owner does not exist must be deleted or given to a user first: {}",
+            names.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Fail with the conflicting names instead of an index error, they have to be renamed by hand
+async fn check_duplicate_names(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let duplicates = Query::select()
+        .expr(Func::lower(Expr::col(Profile::Name)))
+        .from(Profile::Table)
+        .add_group_by([SimpleExpr::from(Func::lower(Expr::col(Profile::Name)))])
+        .and_having(Expr::expr(Func::count(Expr::col(Asterisk))).gt(1))
+        .to_owned();
+    let names = db
+        .query_all(manager.get_database_backend().build(&duplicates))
+        .await?
+        .iter()
+        .map(|row| row.try_get_by_index::<String>(0))
+        .collect::<Result<Vec<String>, DbErr>>()?;
+
+    if !names.is_empty() {
+        return Err(DbErr::Migration(format!(
+            "Profile names differing only by case must be renamed first: {}",
+            names.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// SQLite can not change a column to nullable or add a foreign key, so the table is copied into a new one
+async fn rebuild_sqlite_user_table(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    manager
+        .create_table(
+            Table::create()
+                .table(UserNew::Table)
+                .col(ColumnDef::new(User::Id).string_len(32).not_null().primary_key())
+                .col(ColumnDef::new(User::Email).string().unique_key().not_null())
+                .col(ColumnDef::new(User::Password).string_len(255).not_null())
+                .col(ColumnDef::new(User::ProfileId).string_len(32).null())
+                .col(ColumnDef::new(User::PreferredLanguage).string().null())
+                .col(ColumnDef::new(User::CreateTime).timestamp().default(Expr::current_timestamp()).not_null())
+                .col(ColumnDef::new(User::UpdateTime).timestamp().default(Expr::current_timestamp()).not_null())
+                .col(ColumnDef::new(User::Username).string().null())
+                .col(ColumnDef::new(User::InviteCode).string_len(32).null())
+                .col(ColumnDef::new(User::Verified).boolean().default(true).not_null())
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_user_profile_id")
+                        .from(UserNew::Table, User::ProfileId)
+                        .to(Profile::Table, Profile::Id)
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .on_update(ForeignKeyAction::NoAction),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    db.execute_unprepared(
+        r#"INSERT INTO "user_new" ("id", "email", "password", "profile_id", "preferred_language",
+            "create_time", "update_time", "username", "invite_code", "verified")
+        SELECT "id", "email", "password",
+            CASE WHEN "profile_id" IN (SELECT "id" FROM "profile") THEN "profile_id" END,
+            "preferred_language", "create_time", "update_time", "username", "invite_code", "verified"
+        FROM "user""#,
+    )
+    .await?;
+    manager
+        .drop_table(Table::drop().table(User::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(Table::rename().table(UserNew::Table, User::Table).to_owned())
+        .await
+}
+
+#[derive(DeriveIden, Clone)]
+enum User {
+    Table,
+    Id,
+    Email,
+    Password,
+    ProfileId,
+    PreferredLanguage,
+    CreateTime,
+    UpdateTime,
+    Username,
+    InviteCode,
+    Verified,
+}
+
+#[derive(DeriveIden)]
+enum UserNew {
+    Table,
+}
+
+#[derive(DeriveIden, Clone)]
+enum Profile {
+    Table,
+    Id,
+    Name,
+    OwnerId,
+    SkinTexture,
+    CapeTexture,
+}
+
+#[derive(DeriveIden)]
+enum Texture {
+    Table,
+    Id,
+    Model,
+}
@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, SQLite does not accept several
+        for mut column in [
+            ColumnDef::new(Texture::UploaderId).string_len(32).null().to_owned(),
+            ColumnDef::new(Texture::TextureType).string_len(8).default("skin").not_null().to_owned(),
+            ColumnDef::new(Texture::Width).integer().null().to_owned(),
+            ColumnDef::new(Texture::Height).integer().null().to_owned(),
+            ColumnDef::new(Texture::Size).big_integer().null().to_owned(),
+            ColumnDef::new(Texture::RefCount).integer().default(0).not_null().to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Texture::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // The uploader and the size of earlier textures are unknown
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE texture SET texture_type = 'cape' WHERE model = 'cape'")
+            .await?;
+        db.execute_unprepared(
+            "UPDATE texture SET ref_count = (SELECT COUNT(*) FROM profile \
+            WHERE profile.skin_texture = texture.id OR profile.cape_texture = texture.id)",
+        )
+        .await?;
+
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk_texture_uploader_id")
+                    .from(Texture::Table, Texture::UploaderId)
+                    .to(User::Table, User::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DatabaseBackend::Sqlite {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk_texture_uploader_id")
+                        .table(Texture::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for column in [
+            Texture::UploaderId,
+            Texture::TextureType,
+            Texture::Width,
+            Texture::Height,
+            Texture::Size,
+            Texture::RefCount,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Texture::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(DeriveIden)]
+enum Texture {
+    Table,
+    UploaderId,
+    TextureType,
+    Width,
+    Height,
+    Size,
+    RefCount,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
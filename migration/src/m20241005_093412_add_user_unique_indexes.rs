@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        check_duplicates(manager, User::Username, "Usernames").await?;
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            // The default collation of MySQL already ignores case, the email is unique since the table exists
+            return manager
+                .create_index(
+                    Index::create()
+                        .name("idx_user_username_lower")
+                        .table(User::Table)
+                        .col(User::Username)
+                        .unique()
+                        .to_owned(),
+                )
+                .await;
+        }
+
+        check_duplicates(manager, User::Email, "Emails").await?;
+        db.execute_unprepared(r#"CREATE UNIQUE INDEX idx_user_username_lower ON "user" (LOWER(username))"#)
+            .await?;
+        db.execute_unprepared(r#"CREATE UNIQUE INDEX idx_user_email_lower ON "user" (LOWER(email))"#)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let names: &[&str] = if manager.get_database_backend() == DatabaseBackend::MySql {
+            &["idx_user_username_lower"]
+        } else {
+            &["idx_user_username_lower", "idx_user_email_lower"]
+        };
+        for name in names {
+            manager
+                .drop_index(Index::drop().name(*name).table(User::Table).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fail with the conflicting values instead of an index error, they have to be changed by hand
+async fn check_duplicates(manager: &SchemaManager<'_>, column: User, label: &str) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let duplicates = Query::select()
+        .expr(Func::lower(Expr::col(column.clone())))
+        .from(User::Table)
+        .and_where(Expr::col(column.clone()).is_not_null())
+        .add_group_by([SimpleExpr::from(Func::lower(Expr::col(column)))])
+        .and_having(Expr::expr(Func::count(Expr::col(Asterisk))).gt(1))
+        .to_owned();
+    let values = db
+        .query_all(manager.get_database_backend().build(&duplicates))
+        .await?
+        .iter()
+        .map(|row| row.try_get_by_index::<String>(0))
+        .collect::<Result<Vec<String>, DbErr>>()?;
+
+    if !values.is_empty() {
+        return Err(DbErr::Migration(format!(
+            "{} differing only by case must be changed first: {}",
+            label,
+            values.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+#[derive(DeriveIden, Clone)]
+enum User {
+    Table,
+    Username,
+    Email,
+}
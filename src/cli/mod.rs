@@ -0,0 +1,530 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::EntityTrait;
+
+use crate::model::generated::prelude::Profile;
+use crate::model::generated::profile;
+use crate::service::import::sql::parse_sql_dump;
+use crate::service::import::{import_blessing_skin, parse_json_export, ImportOptions};
+use crate::service::profile::{find_profile_by_name, is_name_taken, is_valid_name};
+use crate::service::token::{invalidate_token, invalidate_tokens};
+use crate::service::{backup, invite, keyring, texture, texture_gc};
+use crate::service::user::{
+    is_email_taken, is_username_taken, is_valid_email, is_valid_password, is_valid_username, NewUser,
+};
+use crate::DATABASE;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Yggdrasil api server, starts the server when no command is given")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage users
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manage profiles
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Inspect and clean up textures
+    #[command(subcommand)]
+    Texture(TextureCommand),
+    /// Manage access tokens
+    #[command(subcommand)]
+    Token(TokenCommand),
+    /// Manage invite codes
+    #[command(subcommand)]
+    Invite(InviteCommand),
+    /// Manage signing keys
+    #[command(subcommand)]
+    Key(KeyCommand),
+    /// Manage database migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Write the database, textures, keys and config into a single archive
+    Export {
+        /// The `.tar.gz` file to create
+        path: PathBuf,
+    },
+    /// Load an archive made by export into an empty database
+    Restore {
+        path: PathBuf,
+        /// Also overwrite the files under `config`
+        #[arg(long)]
+        with_config: bool,
+    },
+    /// Import users, players and textures from a Blessing Skin database
+    Import {
+        /// A MySQL dump of the database
+        #[arg(long, required_unless_present = "json")]
+        sql: Option<PathBuf>,
+        /// A JSON export of the database
+        #[arg(long, conflicts_with = "sql")]
+        json: Option<PathBuf>,
+        /// The `storage/textures` directory, textures have to be copied by hand if not given
+        #[arg(long)]
+        textures: Option<PathBuf>,
+        /// `PWD_METHOD` of Blessing Skin
+        #[arg(long, default_value = "PHP_PASSWORD_HASH")]
+        cipher: String,
+        /// `SALT` of Blessing Skin
+        #[arg(long, default_value = "")]
+        salt: String,
+        /// `DB_PREFIX` of Blessing Skin
+        #[arg(long, default_value = "")]
+        table_prefix: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UserCommand {
+    /// Create a user with its first profile
+    Create {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password: String,
+        /// Name of the first profile
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        username: Option<String>,
+    },
+    /// List every user
+    List,
+    /// Delete a user with all its profiles
+    Delete {
+        /// Id, email or username of the user
+        user: String,
+    },
+    /// Mark the email of a user verified
+    Verify {
+        /// Id, email or username of the user
+        user: String,
+    },
+    /// Set a new password and revoke all tokens of the user
+    ResetPassword {
+        /// Id, email or username of the user
+        user: String,
+        #[arg(long)]
+        password: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommand {
+    /// Rename a profile
+    Rename {
+        /// Id or name of the profile
+        profile: String,
+        name: String,
+    },
+    /// Give a profile to another user
+    Transfer {
+        /// Id or name of the profile
+        profile: String,
+        /// Id, email or username of the new owner
+        user: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TextureCommand {
+    /// List every texture with its uploader, size and number of profiles using it
+    List {
+        /// Id, email or username of the uploader
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Delete the texture files no profile uses anymore
+    Gc {
+        /// Keep unused files younger than this, `gc_grace_hours` of the config if not given
+        #[arg(long)]
+        grace_hours: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TokenCommand {
+    /// Revoke every token of a user, or a single token
+    Revoke {
+        /// Id, email or username of the user
+        user: Option<String>,
+        #[arg(long, conflicts_with = "user")]
+        access_token: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InviteCommand {
+    /// Mint a new invite code
+    Create {
+        /// How many users can register with the code
+        #[arg(long, default_value_t = 1)]
+        max_uses: i32,
+        /// Days before the code expires, never expires if not given
+        #[arg(long)]
+        expire_days: Option<i64>,
+        /// Name of the first profile of the invited user
+        #[arg(long)]
+        profile_name: Option<String>,
+        /// Id, email or username of the inviting user
+        #[arg(long)]
+        creator: Option<String>,
+    },
+    /// List every invite code
+    List,
+    /// Delete an invite code
+    Delete { code: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommand {
+    /// List every signing key
+    List,
+    /// Generate a new signing key and use it right away
+    Rotate,
+    /// Stop publishing a signing key
+    Retire { id: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateCommand {
+    /// Apply pending migrations
+    Up {
+        #[arg(long)]
+        steps: Option<u32>,
+    },
+    /// Rollback applied migrations
+    Down {
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// Show the status of every migration
+    Status,
+}
+
+/// Run a command, exiting the process with a non zero code on failure
+pub async fn run(command: Command) {
+    if let Err(message) = execute(command).await {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
+
+async fn execute(command: Command) -> Result<(), String> {
+    if let Command::Migrate(command) = command {
+        return migrate(command).await;
+    }
+    Migrator::up(&*DATABASE, None).await.map_err(|e| e.to_string())?;
+
+    match command {
+        Command::User(command) => user_command(command).await,
+        Command::Profile(command) => profile_command(command).await,
+        Command::Texture(command) => texture_command(command).await,
+        Command::Token(command) => token_command(command).await,
+        Command::Invite(command) => invite_command(command).await,
+        Command::Key(command) => key_command(command).await,
+        Command::Migrate(_) => unreachable!(),
+        Command::Export { path } => {
+            let report = backup::export_archive(&path).await?;
+            println!(
+                "Exported {} users, {} profiles, {} textures, {} invites and {} files to {}.",
+                report.users, report.profiles, report.textures, report.invites, report.files, path.display()
+            );
+            Ok(())
+        }
+        Command::Restore { path, with_config } => {
+            let report = backup::restore_archive(&path, with_config).await?;
+            println!(
+                "Restored {} users, {} profiles, {} textures, {} invites and {} files.",
+                report.users, report.profiles, report.textures, report.invites, report.files
+            );
+            Ok(())
+        }
+        Command::Import { sql, json, textures, cipher, salt, table_prefix } => {
+            let dump = match (sql, json) {
+                (Some(sql), _) => {
+                    let sql = std::fs::read_to_string(&sql).map_err(|e| format!("Cannot read {}: {}", sql.display(), e))?;
+                    parse_sql_dump(&sql)?
+                }
+                (None, Some(json)) => {
+                    let json = std::fs::read_to_string(&json).map_err(|e| format!("Cannot read {}: {}", json.display(), e))?;
+                    parse_json_export(&json)?
+                }
+                (None, None) => unreachable!(),
+            };
+            let options = ImportOptions {
+                table_prefix,
+                cipher: cipher.to_lowercase(),
+                salt,
+                texture_dir: textures,
+            };
+
+            let report = import_blessing_skin(&dump, &options).await?;
+            for skipped in &report.skipped {
+                println!("Skipped {}", skipped);
+            }
+            println!(
+                "Imported {} users, {} profiles and {} textures.",
+                report.users, report.profiles, report.textures
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn user_command(command: UserCommand) -> Result<(), String> {
+    match command {
+        UserCommand::Create { email, password, name, username } => {
+            if !is_valid_email(&email) {
+                return Err(format!("Invalid email {}.", email));
+            }
+            if is_email_taken(&*DATABASE, &email).await {
+                return Err(format!("Email {} is already used.", email));
+            }
+            if !is_valid_password(&password) {
+                return Err("Invalid password.".to_string());
+            }
+            if let Some(username) = &username {
+                if !is_valid_username(username) {
+                    return Err(format!("Invalid username {}.", username));
+                }
+                if is_username_taken(username).await {
+                    return Err(format!("Username {} is already taken.", username));
+                }
+            }
+            if !is_valid_name(&name) {
+                return Err(format!("Invalid profile name {}.", name));
+            }
+            if is_name_taken(&*DATABASE, &name).await {
+                return Err(format!("Profile name {} is already taken.", name));
+            }
+
+            let new_user = NewUser {
+                email,
+                password,
+                profile_name: name,
+                username,
+                preferred_language: None,
+                invite_code: None,
+                verified: true,
+            };
+            let (user, profile) = crate::service::user::create_user(&*DATABASE, new_user)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Created user {} with profile {} ({}).", user.id, profile.name, profile.id);
+        }
+        UserCommand::List => {
+            for user in crate::service::user::list_users().await {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    user.id,
+                    user.email,
+                    user.username.unwrap_or_default(),
+                    user.create_time
+                );
+            }
+        }
+        UserCommand::Delete { user } => {
+            let user = find_user(&user).await?;
+            let id = user.id.clone();
+            crate::service::user::delete_user(user).await;
+            println!("Deleted user {}.", id);
+        }
+        UserCommand::Verify { user } => {
+            let user = find_user(&user).await?;
+            let user = crate::service::verification::set_verified(user, true).await;
+            println!("Email {} of user {} verified.", user.email, user.id);
+        }
+        UserCommand::ResetPassword { user, password } => {
+            if !is_valid_password(&password) {
+                return Err("Invalid password.".to_string());
+            }
+            let user = find_user(&user).await?;
+            let id = user.id.clone();
+            crate::service::user::reset_password(user, &password).await;
+            println!("Password of user {} reset.", id);
+        }
+    }
+
+    Ok(())
+}
+
+async fn profile_command(command: ProfileCommand) -> Result<(), String> {
+    match command {
+        ProfileCommand::Rename { profile, name } => {
+            let profile = find_profile(&profile).await?;
+            if !is_valid_name(&name) {
+                return Err(format!("Invalid profile name {}.", name));
+            }
+            if !profile.name.eq_ignore_ascii_case(&name) && is_name_taken(&*DATABASE, &name).await {
+                return Err(format!("Profile name {} is already taken.", name));
+            }
+
+            let profile = crate::service::profile::rename_profile(profile, &name)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Profile {} renamed to {}.", profile.id, profile.name);
+        }
+        ProfileCommand::Transfer { profile, user } => {
+            let profile = find_profile(&profile).await?;
+            let user = find_user(&user).await?;
+
+            let profile = crate::service::profile::transfer_profile(profile, &user.id).await;
+            println!("Profile {} transferred to user {}.", profile.name, user.id);
+        }
+    }
+
+    Ok(())
+}
+
+async fn texture_command(command: TextureCommand) -> Result<(), String> {
+    match command {
+        TextureCommand::List { user } => {
+            let uploader_id = match user {
+                Some(user) => Some(find_user(&user).await?.id),
+                None => None,
+            };
+            let unknown = || "-".to_string();
+            for texture in texture::list_textures(uploader_id.as_deref()).await {
+                println!(
+                    "{}\t{}\t{}\t{}x{}\t{}\t{}\t{}\t{}",
+                    texture.id,
+                    texture.texture_type,
+                    texture.model,
+                    texture.width.map_or_else(unknown, |width| width.to_string()),
+                    texture.height.map_or_else(unknown, |height| height.to_string()),
+                    texture.size.map_or_else(unknown, |size| size.to_string()),
+                    texture.ref_count,
+                    texture.uploader_id.unwrap_or_else(unknown),
+                    texture.upload_time
+                );
+            }
+        }
+        TextureCommand::Gc { grace_hours } => {
+            let grace = grace_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+                .unwrap_or_else(texture_gc::default_grace);
+            let report = texture_gc::collect_garbage(grace).await;
+            println!("Deleted {} texture files, {} bytes reclaimed.", report.files, report.bytes);
+        }
+    }
+
+    Ok(())
+}
+
+async fn token_command(command: TokenCommand) -> Result<(), String> {
+    match command {
+        TokenCommand::Revoke { user, access_token } => {
+            if let Some(access_token) = access_token {
+                invalidate_token(&access_token).await;
+                println!("Token revoked.");
+            } else if let Some(user) = user {
+                let user = find_user(&user).await?;
+                invalidate_tokens(&user.id, 0).await;
+                println!("Every token of user {} revoked.", user.id);
+            } else {
+                return Err("Either a user or --access-token is required.".to_string());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn invite_command(command: InviteCommand) -> Result<(), String> {
+    match command {
+        InviteCommand::Create { max_uses, expire_days, profile_name, creator } => {
+            let creator_id = match creator {
+                Some(creator) => Some(find_user(&creator).await?.id),
+                None => None,
+            };
+            let expire_time = expire_days.map(|days| (Utc::now() + chrono::Duration::days(days)).naive_utc());
+
+            let invite = invite::create_invite(creator_id, max_uses, expire_time, profile_name).await?;
+            println!("{}", invite.code);
+        }
+        InviteCommand::List => {
+            for invite in invite::list_invites().await {
+                println!(
+                    "{}\t{}/{}\t{}\t{}",
+                    invite.code,
+                    invite.used_count,
+                    invite.max_uses,
+                    invite.expire_time.map(|time| time.to_string()).unwrap_or_default(),
+                    invite.profile_name.unwrap_or_default()
+                );
+            }
+        }
+        InviteCommand::Delete { code } => {
+            if !invite::delete_invite(&code).await {
+                return Err(format!("Invite {} not found.", code));
+            }
+            println!("Invite {} deleted.", code);
+        }
+    }
+
+    Ok(())
+}
+
+async fn key_command(command: KeyCommand) -> Result<(), String> {
+    match command {
+        KeyCommand::List => {
+            for key in keyring::list_keys() {
+                println!("{}\t{:?}", key.id, key.state);
+            }
+        }
+        KeyCommand::Rotate => {
+            let key = tokio::task::spawn_blocking(keyring::rotate_key)
+                .await
+                .unwrap();
+            println!("Signing key {} is now active.", key.id);
+        }
+        KeyCommand::Retire { id } => {
+            keyring::retire_key(&id).map_err(|e| e.to_string())?;
+            println!("Signing key {} retired.", id);
+        }
+    }
+
+    Ok(())
+}
+
+async fn migrate(command: MigrateCommand) -> Result<(), String> {
+    match command {
+        MigrateCommand::Up { steps } => Migrator::up(&*DATABASE, steps).await,
+        MigrateCommand::Down { steps } => Migrator::down(&*DATABASE, Some(steps)).await,
+        MigrateCommand::Status => {
+            let migrations = Migrator::get_migration_with_status(&*DATABASE)
+                .await
+                .map_err(|e| e.to_string())?;
+            for migration in migrations {
+                println!("{}\t{}", migration.name(), migration.status());
+            }
+            Ok(())
+        }
+    }
+    .map_err(|e| e.to_string())
+}
+
+async fn find_user(identifier: &str) -> Result<crate::model::generated::user::Model, String> {
+    crate::service::user::find_user(identifier)
+        .await
+        .ok_or(format!("User {} not found.", identifier))
+}
+
+async fn find_profile(identifier: &str) -> Result<profile::Model, String> {
+    let profile = match Profile::find_by_id(identifier).one(&*DATABASE).await.unwrap() {
+        Some(profile) => Some(profile),
+        None => find_profile_by_name(identifier).await,
+    };
+
+    profile.ok_or(format!("Profile {} not found.", identifier))
+}
@@ -12,4 +12,79 @@ pub struct AuthConfig {
     pub login_rate_limit: u32,
     #[serde_inline_default(10)]
     pub max_token_allowed: u32,
-}
\ No newline at end of file
+    #[serde_inline_default(5)]
+    pub max_profiles_per_user: u64,
+    #[serde_inline_default(RegistrationMode::Open)]
+    pub registration: RegistrationMode,
+    #[serde_inline_default(8)]
+    pub password_min_length: usize,
+    #[serde_inline_default(TokenStoreType::Database)]
+    pub token_store: TokenStoreType,
+    #[serde_inline_default(String::from("./tokens.json"))]
+    pub token_store_file: String,
+    #[serde_inline_default(false)]
+    pub require_email_verification: bool,
+    #[serde_inline_default(24)]
+    pub verification_token_hours: i64,
+    #[serde_inline_default(30)]
+    pub reset_token_minutes: i64,
+    #[serde_inline_default(true)]
+    pub reverify_changed_email: bool,
+    #[serde_inline_default(PasswordHashConfig::new())]
+    pub password_hash: PasswordHashConfig,
+}
+
+/// Hashes made with other settings are upgraded when their owner logs in
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PasswordHashConfig {
+    #[serde_inline_default(PasswordAlgorithm::Argon2id)]
+    pub algorithm: PasswordAlgorithm,
+    #[serde_inline_default(19456)]
+    pub argon2_memory_kib: u32,
+    #[serde_inline_default(2)]
+    pub argon2_iterations: u32,
+    #[serde_inline_default(1)]
+    pub argon2_parallelism: u32,
+    #[serde_inline_default(12)]
+    pub bcrypt_cost: u32,
+    #[serde_inline_default(600_000)]
+    pub pbkdf2_iterations: u32,
+}
+
+impl PasswordHashConfig {
+    fn new() -> Self {
+        PasswordHashConfig {
+            algorithm: PasswordAlgorithm::Argon2id,
+            argon2_memory_kib: 19456,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            bcrypt_cost: 12,
+            pbkdf2_iterations: 600_000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordAlgorithm {
+    Argon2id,
+    Bcrypt,
+    Pbkdf2,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistrationMode {
+    Open,
+    Closed,
+    Invite,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStoreType {
+    Memory,
+    Database,
+    File,
+}
@@ -18,6 +18,12 @@ pub struct CoreConfig {
     pub ssl_key: String,
     #[serde_inline_default(2)]
     pub max_body_size: usize,
+    #[serde_inline_default(10)]
+    pub max_profile_lookup: usize,
     #[serde_inline_default(String::from("http://127.0.0.1:7890"))]
     pub base_url: String,
+    #[serde_inline_default(7)]
+    pub key_grace_days: i64,
+    #[serde_inline_default(String::new())]
+    pub admin_token: String,
 }
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MailConfig {
+    #[serde_inline_default(MailTransportType::Spool)]
+    pub transport: MailTransportType,
+    #[serde_inline_default(String::from("Yggdrasil <noreply@localhost>"))]
+    pub from: String,
+    #[serde_inline_default(String::from("localhost"))]
+    pub smtp_host: String,
+    #[serde_inline_default(587)]
+    pub smtp_port: u16,
+    #[serde_inline_default(String::new())]
+    pub smtp_username: String,
+    #[serde_inline_default(String::new())]
+    pub smtp_password: String,
+    #[serde_inline_default(SmtpEncryption::StartTls)]
+    pub smtp_encryption: SmtpEncryption,
+    #[serde_inline_default(String::from("./mail"))]
+    pub spool_dir: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransportType {
+    Smtp,
+    /// Write every mail to `spool_dir` instead of sending it
+    Spool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpEncryption {
+    None,
+    StartTls,
+    Tls,
+}
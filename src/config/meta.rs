@@ -17,12 +17,15 @@ pub struct MetaConfig {
 pub struct FeatureConfig {
     #[serde_inline_default(true)]
     pub non_email_login: bool,
+    #[serde_inline_default(true)]
+    pub enable_profile_key: bool,
 }
 
 impl FeatureConfig {
     fn new() -> Self {
         FeatureConfig {
             non_email_login: true,
+            enable_profile_key: true,
         }
     }
 }
\ No newline at end of file
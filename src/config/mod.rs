@@ -8,6 +8,7 @@ pub mod core;
 pub mod auth;
 pub mod texture;
 pub mod meta;
+pub mod mail;
 
 pub fn get_config<T>(name: &str) -> T
 where
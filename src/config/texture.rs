@@ -13,5 +13,68 @@ pub struct TextureConfig {
     #[serde_inline_default(256)]
     pub max_height: u32,
     #[serde_inline_default(vec![String::from("127.0.0.1:7890")])]
-    pub skin_domains: Vec<String>
+    pub skin_domains: Vec<String>,
+    /// Unused texture files younger than this are kept, they may be about to be used
+    #[serde_inline_default(24)]
+    pub gc_grace_hours: u64,
+    /// Hours between two collections of unused texture files, 0 to only collect on demand
+    #[serde_inline_default(24)]
+    pub gc_interval_hours: u64,
+    #[serde_inline_default(TextureStorageType::Local)]
+    pub storage: TextureStorageType,
+    #[serde_inline_default(String::from("./textures"))]
+    pub local_dir: String,
+    #[serde_inline_default(S3Config::new())]
+    pub s3: S3Config,
+}
+
+/// An S3 compatible object storage, like AWS S3 or MinIO
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct S3Config {
+    /// Leave empty for AWS S3, `http://127.0.0.1:9000` for a local MinIO
+    #[serde_inline_default(String::new())]
+    pub endpoint: String,
+    #[serde_inline_default(String::from("us-east-1"))]
+    pub region: String,
+    #[serde_inline_default(String::from("textures"))]
+    pub bucket: String,
+    /// Prepended to the id of every texture to form its key, the texture GC deletes any other object under it
+    #[serde_inline_default(String::new())]
+    pub prefix: String,
+    #[serde_inline_default(String::new())]
+    pub access_key: String,
+    #[serde_inline_default(String::new())]
+    pub secret_key: String,
+    /// Address buckets as `endpoint/bucket` instead of `bucket.endpoint`, required by MinIO
+    #[serde_inline_default(true)]
+    pub path_style: bool,
+    /// Redirect texture downloads to presigned URLs of the storage instead of proxying them
+    #[serde_inline_default(false)]
+    pub presign: bool,
+    #[serde_inline_default(300)]
+    pub presign_seconds: u64,
+}
+
+impl S3Config {
+    fn new() -> Self {
+        S3Config {
+            endpoint: String::new(),
+            region: String::from("us-east-1"),
+            bucket: String::from("textures"),
+            prefix: String::new(),
+            access_key: String::new(),
+            secret_key: String::new(),
+            path_style: true,
+            presign: false,
+            presign_seconds: 300,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureStorageType {
+    Local,
+    S3,
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+use axum::extract::Path;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::admin::authorize_admin;
+use crate::model::generated::invite::Model;
+use crate::service::invite::{create_invite as mint_invite, delete_invite as remove_invite, list_invites as all_invites};
+
+pub async fn list_invites(header_map: HeaderMap) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    let invites: Vec<SerializedInvite> = all_invites()
+        .await
+        .into_iter()
+        .map(SerializedInvite::from)
+        .collect();
+
+    Ok(serde_json::to_string(&invites).unwrap())
+}
+
+pub async fn create_invite(
+    header_map: HeaderMap,
+    Json(request): Json<CreateInviteRequest>,
+) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    let expire_time = request
+        .expire_days
+        .map(|days| (Utc::now() + chrono::Duration::days(days)).naive_utc());
+    let invite = mint_invite(
+        request.creator_id,
+        request.max_uses.unwrap_or(1),
+        expire_time,
+        request.profile_name,
+    )
+    .await
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(serde_json::to_string(&SerializedInvite::from(invite)).unwrap())
+}
+
+pub async fn delete_invite(
+    header_map: HeaderMap,
+    Path(code): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    if !remove_invite(&code).await {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInviteRequest {
+    pub creator_id: Option<String>,
+    pub max_uses: Option<i32>,
+    pub expire_days: Option<i64>,
+    pub profile_name: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedInvite {
+    pub code: String,
+    pub creator_id: Option<String>,
+    pub profile_name: Option<String>,
+    pub max_uses: i32,
+    pub used_count: i32,
+    pub expire_time: Option<i64>,
+    pub create_time: i64,
+}
+
+impl From<Model> for SerializedInvite {
+    fn from(value: Model) -> Self {
+        SerializedInvite {
+            code: value.code,
+            creator_id: value.creator_id,
+            profile_name: value.profile_name,
+            max_uses: value.max_uses,
+            used_count: value.used_count,
+            expire_time: value.expire_time.map(|time| time.and_utc().timestamp_millis()),
+            create_time: value.create_time.and_utc().timestamp_millis(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use axum::extract::Path;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde::Deserialize;
+
+use crate::controller::admin::authorize_admin;
+use crate::service::keyring;
+use crate::service::keyring::KeyringError;
+
+pub async fn list_keys(header_map: HeaderMap) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    Ok(serde_json::to_string(&keyring::list_keys()).unwrap())
+}
+
+pub async fn generate_key(
+    header_map: HeaderMap,
+    request: Option<Json<GenerateKeyRequest>>,
+) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    let entry = tokio::task::spawn_blocking(keyring::generate_key)
+        .await
+        .unwrap();
+    if request.is_some_and(|Json(request)| request.activate.unwrap_or(false)) {
+        keyring::activate_key(&entry.id).map_err(to_status_code)?;
+    }
+
+    Ok(serde_json::to_string(&entry).unwrap())
+}
+
+pub async fn rotate_key(header_map: HeaderMap) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    let entry = tokio::task::spawn_blocking(keyring::rotate_key)
+        .await
+        .unwrap();
+
+    Ok(serde_json::to_string(&entry).unwrap())
+}
+
+pub async fn activate_key(
+    header_map: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    keyring::activate_key(&id).map_err(to_status_code)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn retire_key(
+    header_map: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    keyring::retire_key(&id).map_err(to_status_code)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn to_status_code(error: KeyringError) -> StatusCode {
+    match error {
+        KeyringError::NotFound => StatusCode::NOT_FOUND,
+        KeyringError::IsActive | KeyringError::IsRetired => StatusCode::CONFLICT,
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GenerateKeyRequest {
+    pub activate: Option<bool>,
+}
@@ -0,0 +1,57 @@
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{delete, get, post};
+use axum::Router;
+use subtle::ConstantTimeEq;
+
+use crate::CORE_CONFIG;
+
+mod invite;
+mod key;
+mod texture;
+
+/// Routes for server operators, only available when `admin_token` is configured
+pub fn get_routers() -> Router {
+    Router::new()
+        .route("/keys", get(key::list_keys).post(key::generate_key))
+        .route("/keys/rotate", post(key::rotate_key))
+        .route("/keys/:id/activate", post(key::activate_key))
+        .route("/keys/:id/retire", post(key::retire_key))
+        .route("/invites", get(invite::list_invites).post(invite::create_invite))
+        .route("/invites/:code", delete(invite::delete_invite))
+        .route("/textures/gc", post(texture::collect_garbage))
+}
+
+/// Check the bearer token in the `Authorization` header against `admin_token`
+///
+/// returns: Result<(), StatusCode>: NOT_FOUND if the admin api is disabled, UNAUTHORIZED if the token does not match
+pub fn authorize_admin(header_map: &HeaderMap) -> Result<(), StatusCode> {
+    if CORE_CONFIG.admin_token.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let authorization = header_map
+        .get("Authorization")
+        .and_then(|token| token.to_str().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !is_admin_token(authorization, &CORE_CONFIG.admin_token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(())
+}
+
+/// Compare in constant time, only the length of the configured token can be learned
+fn is_admin_token(authorization: &str, admin_token: &str) -> bool {
+    authorization
+        .strip_prefix("Bearer ")
+        .is_some_and(|token| token.as_bytes().ct_eq(admin_token.as_bytes()).into())
+}
+
+#[test]
+fn test_is_admin_token() {
+    assert!(is_admin_token("Bearer secret", "secret"));
+    assert!(!is_admin_token("secret", "secret"));
+    assert!(!is_admin_token("Bearer other", "secret"));
+    assert!(!is_admin_token("Bearer secretBearer ", "secret"));
+    assert!(!is_admin_token("Bearer ", "secret"));
+}
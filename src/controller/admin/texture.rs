@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde::Deserialize;
+
+use crate::controller::admin::authorize_admin;
+use crate::service::texture_gc;
+
+pub async fn collect_garbage(
+    header_map: HeaderMap,
+    request: Option<Json<CollectGarbageRequest>>,
+) -> Result<String, StatusCode> {
+    authorize_admin(&header_map)?;
+
+    let grace = request
+        .and_then(|Json(request)| request.grace_hours)
+        .map(|hours| Duration::from_secs(hours * 60 * 60))
+        .unwrap_or_else(texture_gc::default_grace);
+    let report = texture_gc::collect_garbage(grace).await;
+
+    Ok(serde_json::to_string(&report).unwrap())
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectGarbageRequest {
+    pub grace_hours: Option<u64>,
+}
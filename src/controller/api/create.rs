@@ -1,36 +1,97 @@
 use axum::Json;
-use sea_orm::{ActiveModelTrait, NotSet};
-use sea_orm::ActiveValue::Set;
-use serde::Deserialize;
-
-use crate::DATABASE;
-use crate::model::serialized::uuid::UuidNoChar;
-use crate::service::password::generate_password_hash;
-
-pub async fn create_user(Json(request): Json<CreateUserRequest>) {
-    let profile_id = UuidNoChar::new().to_string();
-    let user_id = UuidNoChar::new().to_string();
-    crate::model::generated::profile::ActiveModel {
-        id: Set(profile_id.clone()),
-        name: Set(request.name),
-        model: NotSet,
-        owner_id: Set(user_id.clone()),
-        skin_texture: NotSet,
-        cape_texture: NotSet,
-        create_time: NotSet,
-        update_time: NotSet,
-    }.insert(&*DATABASE).await.unwrap();
-    
-    crate::model::generated::user::ActiveModel {
-        id: Set(user_id),
-        username: NotSet,
-        email: Set(request.email),
-        password: Set(generate_password_hash(&request.password)),
-        profile_id: Set(profile_id),
-        preferred_language: Set(request.preferred_language),
-        create_time: NotSet,
-        update_time: NotSet,
-    }.insert(&*DATABASE).await.unwrap();
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::auth::RegistrationMode;
+use crate::controller::api::conflict_response;
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::service::invite::{consume_invite, find_usable_invite};
+use crate::service::profile::{is_name_taken, is_valid_name};
+use crate::service::user::{
+    is_email_taken, is_username_taken, is_valid_email, is_valid_password, is_valid_username,
+    NewUser,
+};
+use crate::service::verification::send_verification_mail;
+use crate::{AUTH_CONFIG, DATABASE};
+
+pub async fn create_user(Json(mut request): Json<CreateUserRequest>) -> Result<String, ErrorResponse> {
+    let invite = match AUTH_CONFIG.registration {
+        RegistrationMode::Open => None,
+        RegistrationMode::Closed => return Err(ErrorResponses::RegistrationClosed.into()),
+        RegistrationMode::Invite => {
+            let code = request
+                .invite_code
+                .as_ref()
+                .ok_or(ErrorResponses::InvalidInviteCode)?;
+            Some(
+                find_usable_invite(code)
+                    .await
+                    .ok_or(ErrorResponses::InvalidInviteCode)?,
+            )
+        }
+    };
+    if let Some(profile_name) = invite.as_ref().and_then(|invite| invite.profile_name.clone()) {
+        request.name = profile_name;
+    }
+
+    if !is_valid_email(&request.email) {
+        return Err(ErrorResponses::InvalidEmail.into());
+    }
+    if !is_valid_password(&request.password) {
+        return Err(ErrorResponses::InvalidPassword.into());
+    }
+    if !is_valid_name(&request.name) {
+        return Err(ErrorResponses::InvalidProfileName.into());
+    }
+    if let Some(username) = &request.username {
+        if !is_valid_username(username) {
+            return Err(ErrorResponses::InvalidUsername.into());
+        }
+        if is_username_taken(username).await {
+            return Err(ErrorResponses::UsernameTaken.into());
+        }
+    }
+    if is_email_taken(&*DATABASE, &request.email).await {
+        return Err(ErrorResponses::EmailTaken.into());
+    }
+    if is_name_taken(&*DATABASE, &request.name).await {
+        return Err(ErrorResponses::ProfileNameTaken.into());
+    }
+
+    let txn = DATABASE.begin().await.unwrap();
+    if let Some(invite) = &invite {
+        // Another registration may have used the last slot since the code was checked
+        if !consume_invite(&txn, &invite.code).await {
+            return Err(ErrorResponses::InvalidInviteCode.into());
+        }
+    }
+    let new_user = NewUser {
+        email: request.email,
+        password: request.password,
+        profile_name: request.name,
+        username: request.username,
+        preferred_language: request.preferred_language,
+        invite_code: invite.map(|invite| invite.code),
+        // Whether unverified users can log in is only decided by `require_email_verification`
+        verified: false,
+    };
+    let (user, profile) = crate::service::user::create_user(&txn, new_user)
+        .await
+        .map_err(conflict_response)?;
+    txn.commit().await.unwrap();
+
+    // The user can ask for another mail, so registration does not fail here
+    if let Err(e) = send_verification_mail(&user).await {
+        warn!("Failed to send verification mail to {}: {}", user.email, e);
+    }
+
+    let response = CreateUserResponse {
+        user_id: user.id,
+        profile_id: profile.id,
+    };
+
+    Ok(serde_json::to_string(&response).unwrap())
 }
 
 #[derive(Deserialize)]
@@ -38,5 +99,13 @@ pub struct CreateUserRequest {
     pub name: String,
     pub email: String,
     pub password: String,
+    pub username: Option<String>,
     pub preferred_language: Option<String>,
-}
\ No newline at end of file
+    pub invite_code: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CreateUserResponse {
+    pub user_id: String,
+    pub profile_id: String,
+}
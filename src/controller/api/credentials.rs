@@ -0,0 +1,97 @@
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::controller::api::{authorize, bearer_token, conflict_response};
+use crate::controller::auth_server::RATE_LIMIT_CACHE;
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::model::generated::prelude::User;
+use crate::model::generated::user;
+use crate::service::password::verify_password;
+use crate::service::user::{is_email_taken, is_valid_email, is_valid_password};
+use crate::service::verification::send_verification_mail;
+use crate::{AUTH_CONFIG, DATABASE};
+
+/// Find the user of the bearer token and check its current password
+async fn authorize_with_password(
+    header_map: &HeaderMap,
+    password: &str,
+) -> Result<user::Model, ErrorResponse> {
+    let token_info = authorize(header_map)
+        .await
+        .ok_or(ErrorResponses::InvalidToken)?;
+    let user = User::find_by_id(&token_info.user_id)
+        .one(&*DATABASE)
+        .await
+        .unwrap()
+        .ok_or(ErrorResponses::InvalidToken)?;
+
+    // Counted with the logins of the email, a stolen token does not allow guessing the password faster
+    let rate = RATE_LIMIT_CACHE.get(&user.email).await.unwrap_or(0);
+    if rate > AUTH_CONFIG.login_rate_limit {
+        return Err(ErrorResponses::InvalidCredentials.into());
+    }
+    RATE_LIMIT_CACHE.insert(user.email.clone(), rate + 1).await;
+
+    if !verify_password(password, &user.password) {
+        return Err(ErrorResponses::InvalidCredentials.into());
+    }
+
+    Ok(user)
+}
+
+pub async fn change_password(
+    header_map: HeaderMap,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    let user = authorize_with_password(&header_map, &request.current_password).await?;
+    if !is_valid_password(&request.new_password) {
+        return Err(ErrorResponses::InvalidPassword.into());
+    }
+
+    let access_token = bearer_token(&header_map).unwrap();
+    crate::service::user::change_password(user, &request.new_password, &access_token).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn change_email(
+    header_map: HeaderMap,
+    Json(request): Json<ChangeEmailRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    let user = authorize_with_password(&header_map, &request.current_password).await?;
+    if !is_valid_email(&request.email) {
+        return Err(ErrorResponses::InvalidEmail.into());
+    }
+    // Changing the case of its own email is allowed
+    if !user.email.eq_ignore_ascii_case(&request.email) && is_email_taken(&*DATABASE, &request.email).await {
+        return Err(ErrorResponses::EmailTaken.into());
+    }
+
+    let user = crate::service::user::change_email(user, &request.email)
+        .await
+        .map_err(conflict_response)?;
+    if !user.verified {
+        if let Err(e) = send_verification_mail(&user).await {
+            warn!("Failed to send verification mail to {}: {}", user.email, e);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEmailRequest {
+    pub current_password: String,
+    pub email: String,
+}
@@ -0,0 +1,38 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::model::serialized::profile::SerializedProfile;
+use crate::service::profile::{find_profile_by_name, find_profiles_by_names, is_valid_name};
+use crate::CORE_CONFIG;
+
+pub async fn lookup_profiles(Json(names): Json<Vec<String>>) -> Result<String, ErrorResponse> {
+    if names.len() > CORE_CONFIG.max_profile_lookup {
+        return Err(ErrorResponses::TooManyNames.into());
+    }
+
+    // Names that can never exist are treated as unknown and simply left out of the response
+    let mut names = names
+        .into_iter()
+        .filter(|name| is_valid_name(name))
+        .collect::<Vec<String>>();
+    names.sort_by_key(|name| name.to_lowercase());
+    names.dedup_by_key(|name| name.to_lowercase());
+
+    let profiles: Vec<SerializedProfile> = find_profiles_by_names(&names)
+        .await
+        .into_iter()
+        .map(SerializedProfile::brief)
+        .collect();
+
+    Ok(serde_json::to_string(&profiles).unwrap())
+}
+
+pub async fn lookup_profile(Path(name): Path<String>) -> Result<String, StatusCode> {
+    let profile = find_profile_by_name(&name)
+        .await
+        .ok_or(StatusCode::NO_CONTENT)?;
+
+    Ok(serde_json::to_string(&SerializedProfile::brief(profile)).unwrap())
+}
@@ -1,11 +1,64 @@
+use axum::http::HeaderMap;
 use axum::Router;
-use axum::routing::{post, put};
+use axum::routing::{delete, get, post, put};
+use sea_orm::DbErr;
+use tracing::error;
+
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::service::token::{check_token_state, get_token_info, TokenInfo, TokenState};
+use crate::service::user::{unique_conflict, Conflict};
 
 mod create;
+mod credentials;
+pub mod lookup;
+mod password;
+mod profile;
 pub mod texture;
+mod verify;
 
 pub fn get_routers() -> Router {
     Router::new()
         .route("/user", post(create::create_user))
-        .route("/user/profile/:uuid/:type", put(texture::upload_texture))
-}
\ No newline at end of file
+        .route("/user/verify", get(verify::verify_email))
+        .route("/user/verify/resend", post(verify::resend_verification))
+        .route("/user/password", post(credentials::change_password))
+        .route("/user/email", post(credentials::change_email))
+        .route("/user/password/reset", post(password::request_password_reset))
+        .route("/user/password/reset/confirm", post(password::confirm_password_reset))
+        .route("/profiles/minecraft", post(lookup::lookup_profiles))
+        .route("/users/profiles/minecraft/:name", get(lookup::lookup_profile))
+        .route("/user/profiles", post(profile::create_profile))
+        .route("/user/profile/:uuid", delete(profile::delete_profile).patch(profile::rename_profile))
+        .route("/user/profile/:uuid/:type", put(texture::upload_texture).delete(texture::delete_texture))
+}
+
+/// Read the bearer token in the `Authorization` header
+pub fn bearer_token(header_map: &HeaderMap) -> Option<String> {
+    let token = header_map.get("Authorization")?.to_str().ok()?;
+    Some(token.replace("Bearer ", ""))
+}
+
+/// Get the token info of the valid bearer token in the `Authorization` header
+///
+/// returns: Option<TokenInfo>: None if the header is missing or the token is not valid
+pub async fn authorize(header_map: &HeaderMap) -> Option<TokenInfo> {
+    let token = bearer_token(header_map)?;
+
+    if check_token_state(&token, None).await != TokenState::Valid {
+        return None;
+    }
+    get_token_info(&token).await
+}
+
+/// The error for a write that lost the race for a unique value, other errors are logged as internal errors
+fn conflict_response(error: DbErr) -> ErrorResponse {
+    match unique_conflict(&error) {
+        Some(Conflict::Email) => ErrorResponses::EmailTaken.into(),
+        Some(Conflict::Username) => ErrorResponses::UsernameTaken.into(),
+        Some(Conflict::ProfileName) => ErrorResponses::ProfileNameTaken.into(),
+        None => {
+            error!("Unexpected database error: {}", error);
+            ErrorResponses::InternalError.into()
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use axum::http::StatusCode;
+use axum::Json;
+use lazy_static::lazy_static;
+use moka::future::Cache;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::service::password_reset::{reset_password_with_token, send_reset_mail};
+use crate::service::user::{find_user_by_email, is_valid_password};
+
+lazy_static! {
+    static ref RESET_CACHE: Cache<String, ()> = Cache::builder()
+        .time_to_live(std::time::Duration::from_secs(60))
+        .build();
+}
+
+/// Mail a reset token to the user
+///
+/// Always answers 204, so it can not tell whether an email is registered.
+pub async fn request_password_reset(Json(request): Json<ResetRequest>) -> StatusCode {
+    let email = request.email.to_lowercase();
+    if RESET_CACHE.contains_key(&email) {
+        return StatusCode::NO_CONTENT;
+    }
+    RESET_CACHE.insert(email, ()).await;
+
+    if !request.email.contains('@') {
+        return StatusCode::NO_CONTENT;
+    }
+    // The user is looked up and mailed in the background, so the answer takes as long for unknown emails
+    tokio::spawn(async move {
+        if let Some(user) = find_user_by_email(&request.email).await {
+            if let Err(e) = send_reset_mail(&user).await {
+                warn!("Failed to send password reset mail to {}: {}", user.email, e);
+            }
+        }
+    });
+
+    StatusCode::NO_CONTENT
+}
+
+pub async fn confirm_password_reset(
+    Json(request): Json<ConfirmResetRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    if !is_valid_password(&request.password) {
+        return Err(ErrorResponses::InvalidPassword.into());
+    }
+    if !reset_password_with_token(&request.token, &request.password).await {
+        return Err(ErrorResponses::InvalidMailToken.into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct ResetRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmResetRequest {
+    pub token: String,
+    pub password: String,
+}
@@ -0,0 +1,83 @@
+use axum::extract::Path;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde::Deserialize;
+
+use crate::controller::api::{authorize, conflict_response};
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::model::serialized::profile::SerializedProfile;
+use crate::service::profile::{count_profiles, get_owned_profile, is_name_taken, is_valid_name};
+use crate::{AUTH_CONFIG, DATABASE};
+
+pub async fn create_profile(
+    header_map: HeaderMap,
+    Json(request): Json<ProfileNameRequest>,
+) -> Result<String, ErrorResponse> {
+    let token_info = authorize(&header_map)
+        .await
+        .ok_or(ErrorResponses::InvalidToken)?;
+
+    if count_profiles(&token_info.user_id).await >= AUTH_CONFIG.max_profiles_per_user {
+        return Err(ErrorResponses::ProfileLimitReached.into());
+    }
+    if !is_valid_name(&request.name) {
+        return Err(ErrorResponses::InvalidProfileName.into());
+    }
+    if is_name_taken(&*DATABASE, &request.name).await {
+        return Err(ErrorResponses::ProfileNameTaken.into());
+    }
+
+    let profile = crate::service::profile::create_profile(&token_info.user_id, &request.name)
+        .await
+        .map_err(conflict_response)?;
+
+    Ok(serde_json::to_string(&SerializedProfile::from(profile)).unwrap())
+}
+
+pub async fn rename_profile(
+    header_map: HeaderMap,
+    Path(profile_id): Path<String>,
+    Json(request): Json<ProfileNameRequest>,
+) -> Result<String, ErrorResponse> {
+    let token_info = authorize(&header_map)
+        .await
+        .ok_or(ErrorResponses::InvalidToken)?;
+    let profile = get_owned_profile(&profile_id, &token_info.user_id)
+        .await
+        .ok_or(ErrorResponses::NoOwnership)?;
+
+    if !is_valid_name(&request.name) {
+        return Err(ErrorResponses::InvalidProfileName.into());
+    }
+    // Changing the case of its own name is allowed
+    if !profile.name.eq_ignore_ascii_case(&request.name) && is_name_taken(&*DATABASE, &request.name).await {
+        return Err(ErrorResponses::ProfileNameTaken.into());
+    }
+
+    let profile = crate::service::profile::rename_profile(profile, &request.name)
+        .await
+        .map_err(conflict_response)?;
+
+    Ok(serde_json::to_string(&SerializedProfile::from(profile)).unwrap())
+}
+
+pub async fn delete_profile(
+    header_map: HeaderMap,
+    Path(profile_id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    let token_info = authorize(&header_map)
+        .await
+        .ok_or(ErrorResponses::InvalidToken)?;
+    let profile = get_owned_profile(&profile_id, &token_info.user_id)
+        .await
+        .ok_or(ErrorResponses::NoOwnership)?;
+
+    crate::service::profile::delete_profile(profile).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProfileNameRequest {
+    pub name: String,
+}
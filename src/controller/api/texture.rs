@@ -1,28 +1,27 @@
-use std::io::Cursor;
 use axum::extract::{Multipart, Path};
-use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
-use image::ImageFormat::Png;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
 use log::debug;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter};
+use sea_orm::{ActiveModelTrait, IntoActiveModel};
 use sea_orm::ActiveValue::Set;
 
-use crate::model::generated::prelude::Profile;
-use crate::service::texture::{read_image, write_file};
-use crate::service::token::get_token_info;
+use crate::controller::api::authorize;
+use crate::model::generated::profile;
+use crate::service::profile::get_owned_profile;
+use crate::service::texture::{
+    file_exists, presigned_url, profile_textures, read_file, save_texture, update_ref_counts, write_file,
+};
+use crate::service::token::TokenInfo;
 use crate::TEXTURE_CONFIG;
 
+// A year, the longest lifetime caches are expected to honour
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
 pub async fn upload_texture(
     header_map: HeaderMap,
     Path((profile_id, texture_type)): Path<(String, String)>,
     mut multipart: Multipart,
 ) -> StatusCode {
-    let token = if let Some(a) = header_map.get("Authorization") {
-        a.to_str().unwrap()
-    } else {
-        return StatusCode::UNAUTHORIZED;
-    };
-    let token = token.replace("Bearer ", "");
-
     if !TEXTURE_CONFIG.allow_cape && texture_type == "cape" {
         return StatusCode::FORBIDDEN;
     }
@@ -30,23 +29,11 @@ pub async fn upload_texture(
         return StatusCode::FORBIDDEN;
     }
 
-    let token_info = if let Some(a) = get_token_info(&token).await {
-        a
-    } else {
-        return StatusCode::UNAUTHORIZED;
+    let (token_info, profile) = match authorize_owner(&header_map, &profile_id).await {
+        Ok(authorized) => authorized,
+        Err(status) => return status,
     };
-
-    let profile = Profile::find()
-        .filter(crate::model::generated::profile::Column::Id.eq(profile_id))
-        .filter(crate::model::generated::profile::Column::OwnerId.eq(token_info.user_id))
-        .one(&*crate::DATABASE)
-        .await
-        .unwrap();
-    if profile.is_none() {
-        return StatusCode::UNAUTHORIZED;
-    }
-    let profile = profile.unwrap();
-    let mut file_id = String::new();
+    let mut file = None;
     let mut model_type = String::new();
 
     while let Some(field) = multipart.next_field().await.unwrap() {
@@ -69,10 +56,10 @@ pub async fn upload_texture(
                     return StatusCode::BAD_REQUEST;
                 }
 
-                let Some(id) = write_file(data).await else {
+                let Some(stored) = write_file(data).await else {
                     return StatusCode::INTERNAL_SERVER_ERROR;
                 };
-                file_id = id;
+                file = Some(stored);
             }
             "model" => {
                 if texture_type != "skin" {
@@ -85,36 +72,122 @@ pub async fn upload_texture(
         }
     }
 
-    if file_id.is_empty() {
+    let Some(file) = file else {
         return StatusCode::BAD_REQUEST;
-    }
+    };
+
+    let texture_model = match texture_type.as_str() {
+        "cape" => "cape",
+        _ if model_type == "slim" => "slim",
+        _ => "default",
+    };
+    save_texture(&file, texture_model, &token_info.user_id).await;
 
+    let mut changed_textures = profile_textures(std::slice::from_ref(&profile));
+    changed_textures.push(file.id.clone());
     let mut profile = profile.into_active_model();
     if texture_type == "skin" {
-        profile.skin_texture = Set(Some(file_id));
+        profile.skin_texture = Set(Some(file.id));
         if !model_type.is_empty() {
             profile.model = Set(model_type);
         }
     } else {
-        profile.cape_texture = Set(Some(file_id));
+        profile.cape_texture = Set(Some(file.id));
     }
     profile.update(&*crate::DATABASE).await.unwrap();
+    update_ref_counts(&*crate::DATABASE, changed_textures).await;
 
     StatusCode::NO_CONTENT
 }
 
-pub async fn get_texture(Path(texture_id): Path<String>) -> Result<(HeaderMap, Vec<u8>), StatusCode> {
-    let image = read_image(&texture_id).await.ok_or(StatusCode::NOT_FOUND)?;
-    let mut buffer = Vec::new();
-    
-    image.write_to(&mut Cursor::new(&mut buffer), Png).unwrap();
-    
+pub async fn delete_texture(
+    header_map: HeaderMap,
+    Path((profile_id, texture_type)): Path<(String, String)>,
+) -> StatusCode {
+    if texture_type != "skin" && texture_type != "cape" {
+        return StatusCode::BAD_REQUEST;
+    }
+    let profile = match authorize_owner(&header_map, &profile_id).await {
+        Ok((_, profile)) => profile,
+        Err(status) => return status,
+    };
+
+    let changed_textures = profile_textures(std::slice::from_ref(&profile));
+    let mut profile = profile.into_active_model();
+    if texture_type == "skin" {
+        profile.skin_texture = Set(None);
+        profile.model = Set("default".to_string());
+    } else {
+        profile.cape_texture = Set(None);
+    }
+    profile.update(&*crate::DATABASE).await.unwrap();
+    update_ref_counts(&*crate::DATABASE, changed_textures).await;
+
+    StatusCode::NO_CONTENT
+}
+
+/// Check the bearer token and that its user owns the profile
+///
+/// returns: Result<(TokenInfo, profile::Model), StatusCode>: UNAUTHORIZED if the token is not valid or the profile is not owned
+async fn authorize_owner(header_map: &HeaderMap, profile_id: &str) -> Result<(TokenInfo, profile::Model), StatusCode> {
+    let token_info = authorize(header_map).await.ok_or(StatusCode::UNAUTHORIZED)?;
+    let profile = get_owned_profile(profile_id, &token_info.user_id)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok((token_info, profile))
+}
+
+/// Serve a texture as stored, ids are content hashes so the response never changes
+pub async fn get_texture(
+    header_map: HeaderMap,
+    Path(texture_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    let etag = format!("\"{}\"", texture_id);
     let mut headers = HeaderMap::new();
-    headers.insert(
-        HeaderName::from_static("content-type"),
-        HeaderValue::from_str("image/png").unwrap(),
-    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(CACHE_CONTROL));
+    headers.insert(header::ETAG, HeaderValue::from_str(&etag).map_err(|_| StatusCode::NOT_FOUND)?);
 
+    let if_none_match = header_map
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if if_none_match.is_some_and(|value| etag_matches(value, &etag)) {
+        // A deleted texture must not stay valid in caches
+        if !file_exists(&texture_id).await {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    if let Some(url) = presigned_url(&texture_id).await {
+        // The address expires, so the redirect is cached for half its validity. It has no ETag, a 304 would
+        // renew a cached redirect whose address already expired.
+        let cache_control = format!("public, max-age={}", TEXTURE_CONFIG.s3.presign_seconds / 2);
+        return Ok(([(header::CACHE_CONTROL, cache_control)], Redirect::temporary(&url)).into_response());
+    }
+
+    let content = read_file(&texture_id).await.ok_or(StatusCode::NOT_FOUND)?;
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
 
-    Ok((headers, buffer))
-}
\ No newline at end of file
+    Ok((headers, content).into_response())
+}
+
+/// Whether an `If-None-Match` header lists the tag, weak comparison as required for this header
+///
+/// `*` matches any tag, the caller checks that the texture exists.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag.trim_start_matches("W/") == etag)
+}
+
+#[test]
+fn test_etag_matches() {
+    assert!(etag_matches("\"abc\"", "\"abc\""));
+    assert!(etag_matches("\"xyz\", W/\"abc\"", "\"abc\""));
+    assert!(!etag_matches("\"abcd\"", "\"abc\""));
+    assert!(!etag_matches("abc", "\"abc\""));
+    assert!(etag_matches(" * ", "\"abc\""));
+}
@@ -0,0 +1,64 @@
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use lazy_static::lazy_static;
+use moka::future::Cache;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::service::user::find_user;
+use crate::service::verification::send_verification_mail;
+
+lazy_static! {
+    // One mail per address and minute, so the endpoint can not be used to flood an inbox
+    static ref RESEND_CACHE: Cache<String, ()> = Cache::builder()
+        .time_to_live(std::time::Duration::from_secs(60))
+        .build();
+}
+
+/// Target of the link in the verification mail
+pub async fn verify_email(Query(query): Query<VerifyQuery>) -> Result<String, ErrorResponse> {
+    let user = crate::service::verification::verify_email(&query.token)
+        .await
+        .ok_or(ErrorResponses::InvalidMailToken)?;
+
+    Ok(format!("Email {} verified.", user.email))
+}
+
+/// Send the verification mail again
+///
+/// Always answers 204, so it can not tell whether an email is registered.
+pub async fn resend_verification(Json(request): Json<ResendRequest>) -> StatusCode {
+    let email = request.email.to_lowercase();
+    if RESEND_CACHE.contains_key(&email) {
+        return StatusCode::NO_CONTENT;
+    }
+    RESEND_CACHE.insert(email, ()).await;
+
+    if !request.email.contains('@') {
+        return StatusCode::NO_CONTENT;
+    }
+    // The user is looked up and mailed in the background, so the answer takes as long for unknown emails
+    tokio::spawn(async move {
+        if let Some(user) = find_user(&request.email).await {
+            if !user.verified {
+                if let Err(e) = send_verification_mail(&user).await {
+                    warn!("Failed to send verification mail to {}: {}", user.email, e);
+                }
+            }
+        }
+    });
+
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+pub struct VerifyQuery {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendRequest {
+    pub email: String,
+}
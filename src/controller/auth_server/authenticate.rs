@@ -1,16 +1,17 @@
 use axum::Json;
-use sea_orm::{ColumnTrait, EntityTrait};
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait};
 use sea_orm::QueryFilter;
 use serde::{Deserialize, Serialize};
 
 use crate::{AUTH_CONFIG, DATABASE, META_CONFIG};
 use crate::controller::{ErrorResponse, ErrorResponses};
 use crate::controller::auth_server::RATE_LIMIT_CACHE;
-use crate::model::generated::prelude::User;
+use crate::model::generated::prelude::{Profile, User};
 use crate::model::serialized::profile::SerializedProfile;
 use crate::model::serialized::user::SerializedUser;
-use crate::service::password::verify_password;
+use crate::service::password::{needs_rehash, verify_user_password};
 use crate::service::token::sign_new_token;
+use crate::service::user::rehash_password;
 
 pub async fn authenticate(
     Json(request): Json<AuthenticateRequest>,
@@ -27,36 +28,53 @@ pub async fn authenticate(
             .one(&*DATABASE)
             .await
             .unwrap()
-            .ok_or(ErrorResponses::InvalidCredentials)? 
     } else if META_CONFIG.feature.non_email_login { 
         User::find()
             .filter(crate::model::generated::user::Column::Username.eq(request.username))
             .one(&*DATABASE)
             .await
             .unwrap()
-            .ok_or(ErrorResponses::InvalidCredentials)?
     } else { 
         return Err(ErrorResponses::InvalidCredentials.into());
     };
 
-    if !verify_password(&request.password, &user.password) {
+    // Unknown users are checked against a dummy hash, so timing does not tell whether an account exists
+    let password_hash = user.as_ref().map(|user| user.password.as_str());
+    if !verify_user_password(&request.password, password_hash) {
         return Err(ErrorResponses::InvalidCredentials.into());
     }
+    let user = user.ok_or(ErrorResponses::InvalidCredentials)?;
+    // The password is only known right now, so outdated hashes are upgraded on login
+    let user = if needs_rehash(&user.password, &AUTH_CONFIG.password_hash) {
+        rehash_password(user, &request.password).await
+    } else {
+        user
+    };
+    if AUTH_CONFIG.require_email_verification && !user.verified {
+        return Err(ErrorResponses::EmailNotVerified.into());
+    }
 
-    let (access_token, client_token) = sign_new_token(user.id.clone(), request.client_token).await;
-
-    let profiles: Vec<SerializedProfile> = crate::model::generated::profile::Entity::find()
-        .filter(crate::model::generated::profile::Column::OwnerId.eq(user.id.clone()))
+    let profiles: Vec<SerializedProfile> = user
+        .find_related(Profile)
         .all(&*DATABASE)
         .await
         .unwrap()
         .into_iter()
         .map(|profile| SerializedProfile::from(profile.clone()))
         .collect();
-    let selected_profile = profiles
-        .iter()
-        .find(|profile| profile.id == user.profile_id)
-        .cloned();
+    // Only select a profile on behalf of the user when there is no choice to make
+    let selected_profile = if profiles.len() == 1 {
+        profiles.first().cloned()
+    } else {
+        None
+    };
+
+    let (access_token, client_token) = sign_new_token(
+        user.id.clone(),
+        request.client_token,
+        selected_profile.as_ref().map(|profile| profile.id.clone()),
+    )
+    .await;
 
     let user = SerializedUser::from(user);
     let response = AuthenticateResponse {
@@ -14,5 +14,6 @@ pub async fn invalidate(Json(request): Json<InvalidateRequest>) -> StatusCode {
 #[serde(rename_all = "camelCase")]
 pub struct InvalidateRequest {
     pub access_token: String,
+    #[allow(dead_code)]
     pub client_token: Option<String>,
 }
@@ -19,7 +19,7 @@ pub fn get_routers() -> Router {
 }
 
 lazy_static!{
-    static ref RATE_LIMIT_CACHE: Cache<String, u32> = Cache::builder()
+    pub static ref RATE_LIMIT_CACHE: Cache<String, u32> = Cache::builder()
         .time_to_live(std::time::Duration::from_secs(60))
         .build();
 }
\ No newline at end of file
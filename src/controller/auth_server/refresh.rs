@@ -1,6 +1,5 @@
 use axum::Json;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter};
-use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 
 use crate::controller::{ErrorResponse, ErrorResponses};
@@ -25,7 +24,11 @@ pub async fn refresh(Json(request): Json<RefreshRequest>) -> Result<String, Erro
         .unwrap()
         .ok_or(ErrorResponses::InvalidToken)?;
 
-    if let Some(profile) = request.selected_profile {
+    let profile_id = if let Some(profile) = request.selected_profile {
+        if token_info.profile_id.is_some() {
+            return Err(ErrorResponses::AlreadyBind.into());
+        }
+
         let profile = Profile::find()
             .filter(crate::model::generated::profile::Column::Id.eq(profile.id))
             .one(&*DATABASE)
@@ -37,14 +40,28 @@ pub async fn refresh(Json(request): Json<RefreshRequest>) -> Result<String, Erro
             return Err(ErrorResponses::NoOwnership.into());
         }
 
-        let mut user = user.clone().into_active_model();
-        user.profile_id = Set(profile.id.clone());
-        user.update(&*DATABASE).await.unwrap();
-    }
+        Some(profile.id)
+    } else {
+        token_info.profile_id.clone()
+    };
+
+    let selected_profile = match &profile_id {
+        Some(profile_id) => Profile::find()
+            .filter(crate::model::generated::profile::Column::Id.eq(profile_id))
+            .one(&*DATABASE)
+            .await
+            .unwrap()
+            .map(SerializedProfile::from),
+        None => None,
+    };
 
     invalidate_token(&request.access_token).await;
-    let (access_token, client_token) =
-        sign_new_token(token_info.user_id.clone(), Some(token_info.client_token)).await;
+    let (access_token, client_token) = sign_new_token(
+        token_info.user_id.clone(),
+        Some(token_info.client_token),
+        selected_profile.as_ref().map(|profile| profile.id.clone()),
+    )
+    .await;
     
     let user = if request.request_user.is_some() && request.request_user.unwrap() { 
         Some(SerializedUser::from(user))
@@ -55,7 +72,7 @@ pub async fn refresh(Json(request): Json<RefreshRequest>) -> Result<String, Erro
     let response = RefreshResponse {
         access_token,
         client_token,
-        selected_profile: None,
+        selected_profile,
         user,
     };
 
@@ -0,0 +1,25 @@
+use axum::http::{HeaderMap, StatusCode};
+
+use crate::controller::api::authorize;
+use crate::service::certificate::{get_player_certificate, get_public_keys as public_keys};
+use crate::META_CONFIG;
+
+pub async fn get_certificate(header_map: HeaderMap) -> Result<String, StatusCode> {
+    if !META_CONFIG.feature.enable_profile_key {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let token_info = authorize(&header_map)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    // Chat is signed by a profile, so a token without a bound profile can not get a certificate
+    let profile_id = token_info.profile_id.ok_or(StatusCode::FORBIDDEN)?;
+
+    let certificate = get_player_certificate(&profile_id).await;
+
+    Ok(serde_json::to_string(&certificate).unwrap())
+}
+
+pub async fn get_public_keys() -> String {
+    serde_json::to_string(&public_keys()).unwrap()
+}
@@ -0,0 +1,18 @@
+use axum::routing::{get, post};
+use axum::Router;
+
+use crate::controller::api::lookup;
+
+mod certificate;
+mod profile;
+
+/// Routes of `api.minecraftservices.com`, which authlib-injector redirects to `/minecraftservices`
+pub fn get_routers() -> Router {
+    Router::new()
+        .route("/minecraft/profile/lookup/name/:name", get(profile::lookup_profile))
+        .route("/minecraft/profile/lookup/bulk/byname", post(lookup::lookup_profiles))
+        .route("/player/certificates", post(certificate::get_certificate))
+        .route("/minecraft/profile/certificates", post(certificate::get_certificate))
+        .route("/publickeys", get(certificate::get_public_keys))
+        .route("/minecraft/services/publickeys", get(certificate::get_public_keys))
+}
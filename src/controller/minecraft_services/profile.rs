@@ -0,0 +1,13 @@
+use axum::extract::Path;
+
+use crate::controller::{ErrorResponse, ErrorResponses};
+use crate::model::serialized::profile::SerializedProfile;
+use crate::service::profile::find_profile_by_name;
+
+pub async fn lookup_profile(Path(name): Path<String>) -> Result<String, ErrorResponse> {
+    let profile = find_profile_by_name(&name)
+        .await
+        .ok_or(ErrorResponses::ProfileNotFound.to_error_response(Some(name)))?;
+
+    Ok(serde_json::to_string(&SerializedProfile::brief(profile)).unwrap())
+}
@@ -7,15 +7,17 @@ use rsa::pkcs8::{EncodePublicKey, LineEnding};
 use serde::Serialize;
 use serde_json::{Map, Value};
 use shadow_rs::shadow;
-use crate::{META_CONFIG, TEXTURE_CONFIG};
-use crate::service::crypto::SIGNATURE_KEY_PAIR;
+use crate::{AUTH_CONFIG, META_CONFIG, TEXTURE_CONFIG};
+use crate::service::keyring::active_public_key;
 
+mod admin;
 mod api;
 mod auth_server;
+mod minecraft_services;
 mod session_server;
 
 lazy_static! {
-    static ref PING_META: String = {
+    static ref PING_META: Value = {
         shadow!(build);
 
         let version = format!("{}-{}", build::SHORT_COMMIT, build::BUILD_RUST_CHANNEL);
@@ -30,18 +32,11 @@ lazy_static! {
         if META_CONFIG.feature.non_email_login {
             meta.insert("feature.non_email_login".to_string(), Value::Bool(true));
         }
+        if META_CONFIG.feature.enable_profile_key {
+            meta.insert("feature.enable_profile_key".to_string(), Value::Bool(true));
+        }
 
-        let meta = PingMeta {
-            meta: Value::Object(meta),
-            skin_domains: TEXTURE_CONFIG.skin_domains.clone(),
-            signature_publickey: SIGNATURE_KEY_PAIR
-                .1
-                .to_public_key_pem(LineEnding::LF)
-                .unwrap()
-                .to_string(),
-        };
-
-        serde_json::to_string(&meta).unwrap()
+        Value::Object(meta)
     };
 }
 
@@ -52,10 +47,22 @@ pub fn all_routers() -> Router {
         .nest("/api", api::get_routers())
         .nest("/authserver", auth_server::get_routers())
         .nest("/sessionserver/session", session_server::get_routers())
+        .nest("/minecraftservices", minecraft_services::get_routers())
+        .nest("/admin", admin::get_routers())
 }
 
-pub async fn ping() -> &'static str {
-    &PING_META
+pub async fn ping() -> String {
+    // The signing key may be rotated at runtime, so it is not part of the cached meta
+    let meta = PingMeta {
+        meta: PING_META.clone(),
+        skin_domains: TEXTURE_CONFIG.skin_domains.clone(),
+        signature_publickey: active_public_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap()
+            .to_string(),
+    };
+
+    serde_json::to_string(&meta).unwrap()
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -72,6 +79,21 @@ pub enum ErrorResponses {
     AlreadyBind,        //试图向一个已经绑定了角色的令牌指定其要绑定的角色
     NoOwnership,        //试图向一个令牌绑定不属于其对应用户的角色 （非标准）
     InvalidProfile,     //试图使用一个错误的角色加入服务器
+    InvalidProfileName, //角色名不合法 （非标准）
+    ProfileNameTaken,   //角色名已被占用 （非标准）
+    ProfileLimitReached, //用户拥有的角色数量已达上限 （非标准）
+    TooManyNames,       //批量查询的角色名数量超过上限
+    ProfileNotFound,    //找不到对应名称的角色
+    RegistrationClosed, //注册已关闭 （非标准）
+    InvalidInviteCode,  //邀请码无效 （非标准）
+    InvalidEmail,       //邮箱格式不正确 （非标准）
+    InvalidPassword,    //密码不符合要求 （非标准）
+    InvalidUsername,    //用户名不合法 （非标准）
+    EmailTaken,         //邮箱已被注册 （非标准）
+    UsernameTaken,      //用户名已被占用 （非标准）
+    EmailNotVerified,   //邮箱尚未验证 （非标准）
+    InvalidMailToken,   //邮件中的令牌无效或已过期 （非标准）
+    InternalError,      //服务器内部错误 （非标准）
 }
 
 impl ErrorResponses {
@@ -107,6 +129,99 @@ impl ErrorResponses {
                 error_message: "Invalid token.".to_string(),
                 cause,
             },
+            ErrorResponses::InvalidProfileName => ErrorResponse {
+                http_code: StatusCode::BAD_REQUEST,
+                error: "IllegalArgumentException".to_string(),
+                error_message: "Invalid profile name.".to_string(),
+                cause,
+            },
+            ErrorResponses::ProfileNameTaken => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Profile name already taken.".to_string(),
+                cause,
+            },
+            ErrorResponses::ProfileLimitReached => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Profile limit reached.".to_string(),
+                cause,
+            },
+            ErrorResponses::TooManyNames => ErrorResponse {
+                http_code: StatusCode::BAD_REQUEST,
+                error: "IllegalArgumentException".to_string(),
+                error_message: "Too many names requested.".to_string(),
+                cause,
+            },
+            ErrorResponses::ProfileNotFound => ErrorResponse {
+                http_code: StatusCode::NOT_FOUND,
+                error: "NotFoundException".to_string(),
+                error_message: "Couldn't find any profile with that name.".to_string(),
+                cause,
+            },
+            ErrorResponses::RegistrationClosed => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Registration is closed.".to_string(),
+                cause,
+            },
+            ErrorResponses::InvalidInviteCode => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Invalid invite code.".to_string(),
+                cause,
+            },
+            ErrorResponses::InvalidEmail => ErrorResponse {
+                http_code: StatusCode::BAD_REQUEST,
+                error: "IllegalArgumentException".to_string(),
+                error_message: "Invalid email.".to_string(),
+                cause,
+            },
+            ErrorResponses::InvalidPassword => ErrorResponse {
+                http_code: StatusCode::BAD_REQUEST,
+                error: "IllegalArgumentException".to_string(),
+                error_message: format!(
+                    "Password must be at least {} characters long.",
+                    AUTH_CONFIG.password_min_length
+                ),
+                cause,
+            },
+            ErrorResponses::InvalidUsername => ErrorResponse {
+                http_code: StatusCode::BAD_REQUEST,
+                error: "IllegalArgumentException".to_string(),
+                error_message: "Invalid username.".to_string(),
+                cause,
+            },
+            ErrorResponses::EmailTaken => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Email already registered.".to_string(),
+                cause,
+            },
+            ErrorResponses::UsernameTaken => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Username already taken.".to_string(),
+                cause,
+            },
+            ErrorResponses::EmailNotVerified => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Email not verified.".to_string(),
+                cause,
+            },
+            ErrorResponses::InvalidMailToken => ErrorResponse {
+                http_code: StatusCode::FORBIDDEN,
+                error: "ForbiddenOperationException".to_string(),
+                error_message: "Invalid or expired token.".to_string(),
+                cause,
+            },
+            ErrorResponses::InternalError => ErrorResponse {
+                http_code: StatusCode::INTERNAL_SERVER_ERROR,
+                error: "InternalServerError".to_string(),
+                error_message: "Internal server error.".to_string(),
+                cause,
+            },
         }
     }
 }
@@ -119,6 +234,7 @@ pub struct ErrorResponse {
 }
 
 impl ErrorResponse {
+    #[allow(dead_code)]
     fn with_cause(self, cause: Option<String>) -> ErrorResponse {
         ErrorResponse { cause, ..self }
     }
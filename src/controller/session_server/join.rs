@@ -7,7 +7,7 @@ use std::net::SocketAddr;
 use tracing::debug;
 
 use crate::controller::{ErrorResponse, ErrorResponses};
-use crate::model::generated::prelude::{Profile, User};
+use crate::model::generated::prelude::Profile;
 use crate::model::serialized::profile::SerializedProfile;
 use crate::service::session::{get_session_info, save_session, SessionInfo};
 use crate::service::token::TokenState::Valid;
@@ -22,20 +22,14 @@ pub async fn join_server(
         return Err(ErrorResponses::InvalidToken.into());
     }
     let token_info = get_token_info(&request.access_token).await.unwrap();
-    let user = User::find()
-        .filter(crate::model::generated::user::Column::Id.eq(&token_info.user_id))
-        .one(&*DATABASE)
-        .await
-        .unwrap()
-        .ok_or(ErrorResponses::InvalidToken)?;
 
-    if user.profile_id != request.selected_profile {
-        return Err(ErrorResponses::AlreadyBind.into());
+    if token_info.profile_id.as_ref() != Some(&request.selected_profile) {
+        return Err(ErrorResponses::InvalidProfile.into());
     }
 
     debug!(
         "Player {} joined the server {} at {}.",
-        user.id,
+        request.selected_profile,
         request.server_id,
         addr.to_string()
     );
@@ -60,19 +54,13 @@ pub async fn has_joined_server(
         return Err(StatusCode::NO_CONTENT);
     }
 
-    let user = get_token_info(&session_info.access_token)
-        .await
-        .ok_or(StatusCode::NO_CONTENT)?;
-
-    let user = User::find()
-        .filter(crate::model::generated::user::Column::Id.eq(&user.user_id))
-        .one(&*DATABASE)
+    let profile_id = get_token_info(&session_info.access_token)
         .await
-        .unwrap()
+        .and_then(|token_info| token_info.profile_id)
         .ok_or(StatusCode::NO_CONTENT)?;
 
     let mut profile: SerializedProfile = Profile::find()
-        .filter(crate::model::generated::profile::Column::Id.eq(&user.profile_id))
+        .filter(crate::model::generated::profile::Column::Id.eq(&profile_id))
         .one(&*DATABASE)
         .await
         .unwrap()
@@ -1,9 +1,7 @@
-use std::fmt::Debug;
-use axum::body::Body;
 use axum::extract::DefaultBodyLimit;
-use axum::http;
 use axum::http::{HeaderName, HeaderValue};
 use axum_server::tls_rustls::RustlsConfig;
+use clap::Parser;
 use lazy_static::lazy_static;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 use std::net::SocketAddr;
@@ -13,7 +11,7 @@ use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace;
 use tower_http::trace::TraceLayer;
 use tracing::log::{warn, LevelFilter};
-use tracing::{debug, info, Level, Span};
+use tracing::{debug, info, Level};
 use tracing_appender::non_blocking;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::time::ChronoLocal;
@@ -23,12 +21,15 @@ use tracing_subscriber::{fmt, EnvFilter, Registry};
 
 use migration::{Migrator, MigratorTrait};
 
+use crate::cli::Cli;
 use crate::config::auth::AuthConfig;
 use crate::config::core::CoreConfig;
 use crate::config::get_config;
+use crate::config::mail::MailConfig;
 use crate::config::meta::MetaConfig;
 use crate::config::texture::TextureConfig;
 
+mod cli;
 mod config;
 mod controller;
 mod model;
@@ -39,6 +40,7 @@ lazy_static! {
     static ref AUTH_CONFIG: AuthConfig = get_config("auth");
     static ref TEXTURE_CONFIG: TextureConfig = get_config("textures");
     static ref META_CONFIG: MetaConfig = get_config("meta");
+    static ref MAIL_CONFIG: MailConfig = get_config("mail");
     static ref DATABASE: DatabaseConnection = {
         let mut opt = ConnectOptions::new(&CORE_CONFIG.db_uri);
         opt.sqlx_logging(true);
@@ -54,6 +56,8 @@ lazy_static! {
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(&CORE_CONFIG.trace_level));
     let file_appender = RollingFileAppender::builder()
@@ -76,7 +80,43 @@ async fn main() {
         .with(file_layer)
         .init();
 
+    if let Some(command) = cli.command {
+        cli::run(command).await;
+        return;
+    }
+
     Migrator::up(&*DATABASE, None).await.unwrap();
+    service::password::init().await;
+    // A missing profile key takes seconds to generate, which must not block a worker serving requests
+    tokio::task::spawn_blocking(|| lazy_static::initialize(&service::crypto::PROFILE_KEY_PAIR))
+        .await
+        .unwrap();
+
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            service::token::purge_expired_tokens().await;
+        }
+    });
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            tokio::task::spawn_blocking(service::keyring::refresh).await.unwrap();
+        }
+    });
+    if TEXTURE_CONFIG.gc_interval_hours > 0 {
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                TEXTURE_CONFIG.gc_interval_hours * 60 * 60,
+            ));
+            loop {
+                interval.tick().await;
+                service::texture_gc::collect_garbage(service::texture_gc::default_grace()).await;
+            }
+        });
+    }
 
     let trace_layer =
         TraceLayer::new(StatusInRangeAsFailures::new(400..=599).into_make_classifier())
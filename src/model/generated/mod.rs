@@ -2,6 +2,8 @@
 
 pub mod prelude;
 
+pub mod invite;
 pub mod profile;
 pub mod texture;
+pub mod token;
 pub mod user;
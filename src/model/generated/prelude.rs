@@ -1,5 +1,7 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+pub use super::invite::Entity as Invite;
 pub use super::profile::Entity as Profile;
 pub use super::texture::Entity as Texture;
+pub use super::token::Entity as Token;
 pub use super::user::Entity as User;
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerCertificate {
+    pub key_pair: KeyPair,
+    pub public_key_signature: String,
+    pub public_key_signature_v2: String,
+    pub expires_at: String,
+    pub refreshed_after: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPair {
+    pub private_key: String,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeys {
+    pub profile_property_keys: Vec<PublicKey>,
+    pub player_certificate_keys: Vec<PublicKey>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKey {
+    pub public_key: String,
+}
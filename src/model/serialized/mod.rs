@@ -1,4 +1,5 @@
 pub mod user;
 pub mod properties;
 pub mod uuid;
-pub mod profile;
\ No newline at end of file
+pub mod profile;
+pub mod certificate;
\ No newline at end of file
@@ -38,6 +38,15 @@ pub struct TextureMeta {
 }
 
 impl SerializedProfile {
+    /// Only the id and the name of the profile, used by name lookups
+    pub fn brief(value: Model) -> Self {
+        SerializedProfile {
+            id: value.id,
+            name: value.name,
+            properties: vec![],
+        }
+    }
+
     pub async fn sign(&mut self) {
         for property in self.properties.iter_mut() {
             property.signature = Some(rsa_sign(property.value.as_bytes()));
@@ -12,10 +12,10 @@ pub struct SerializedUser {
 impl From<Model> for SerializedUser {
     fn from(value: Model) -> Self {
         let mut properties = vec![];
-        if value.preferred_language.is_some() {
+        if let Some(preferred_language) = value.preferred_language {
             properties.push(Properties {
                 name: "preferred_language".to_string(),
-                value: value.preferred_language.unwrap(),
+                value: preferred_language,
                 signature: None
             })
         }
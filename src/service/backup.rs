@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseTransaction, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter,
+    TransactionTrait,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::model::generated::prelude::{Invite, Profile, Texture, User};
+use crate::model::generated::{invite, profile, texture, user};
+use crate::service::texture_storage::texture_storage;
+use crate::DATABASE;
+
+const ARCHIVE_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+// Directories copied as they are, tokens are not part of a backup so everyone logs in again
+const FILE_DIRS: [&str; 1] = ["keys"];
+const CONFIG_DIR: &str = "config";
+// Texture files are read from the texture storage, laid out as the local storage
+const TEXTURE_DIR: &str = "textures";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    create_time: i64,
+    /// Migrations applied to the exported database, the data is only valid for the same schema
+    migrations: Vec<String>,
+}
+
+#[derive(Default, Debug)]
+pub struct BackupReport {
+    pub users: usize,
+    pub profiles: usize,
+    pub textures: usize,
+    pub invites: usize,
+    pub files: usize,
+}
+
+fn data_path(table: &str) -> String {
+    format!("data/{}.json", table)
+}
+
+/// None for an id too short to be stored this way
+fn texture_path(id: &str) -> Option<String> {
+    Some(format!("{}/{}/{}", TEXTURE_DIR, id.get(0..2)?.to_ascii_lowercase(), id))
+}
+
+fn append_json<T: Serialize>(
+    builder: &mut tar::Builder<GzEncoder<File>>,
+    path: &str,
+    value: &T,
+) -> Result<(), String> {
+    append_file(builder, path, &serde_json::to_vec_pretty(value).unwrap())
+}
+
+fn append_file(
+    builder: &mut tar::Builder<GzEncoder<File>>,
+    path: &str,
+    content: &[u8],
+) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, content)
+        .map_err(|e| e.to_string())
+}
+
+/// Write the database, texture files, signing keys and config into a single `.tar.gz` archive
+///
+/// Texture files are read from the configured texture storage, so an archive can move a server
+/// between storages.
+pub async fn export_archive(path: &Path) -> Result<BackupReport, String> {
+    let migrations = Migrator::get_applied_migrations(&*DATABASE)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
+    let users = User::find().all(&*DATABASE).await.map_err(|e| e.to_string())?;
+    let profiles = Profile::find().all(&*DATABASE).await.map_err(|e| e.to_string())?;
+    let textures = Texture::find().all(&*DATABASE).await.map_err(|e| e.to_string())?;
+    let invites = Invite::find().all(&*DATABASE).await.map_err(|e| e.to_string())?;
+
+    let file = File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let manifest = Manifest {
+        version: ARCHIVE_VERSION,
+        create_time: Utc::now().timestamp_millis(),
+        migrations,
+    };
+    append_json(&mut builder, MANIFEST, &manifest)?;
+    append_json(&mut builder, &data_path("user"), &users)?;
+    append_json(&mut builder, &data_path("profile"), &profiles)?;
+    append_json(&mut builder, &data_path("texture"), &textures)?;
+    append_json(&mut builder, &data_path("invite"), &invites)?;
+
+    let mut report = BackupReport {
+        users: users.len(),
+        profiles: profiles.len(),
+        textures: textures.len(),
+        invites: invites.len(),
+        files: 0,
+    };
+    let storage = texture_storage();
+    for object in storage.list().await? {
+        let Some(path) = texture_path(&object.id) else {
+            continue;
+        };
+        let Some(content) = storage.get(&object.id).await? else {
+            continue;
+        };
+        append_file(&mut builder, &path, &content)?;
+        report.files += 1;
+    }
+    for dir in FILE_DIRS.iter().chain([CONFIG_DIR].iter()) {
+        if !Path::new(dir).is_dir() {
+            continue;
+        }
+        report.files += count_files(Path::new(dir));
+        builder.append_dir_all(dir, dir).map_err(|e| e.to_string())?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+fn count_files(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        count_files(&path)
+                    } else {
+                        1
+                    }
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<GzDecoder<File>>, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+fn parse<T: DeserializeOwned>(data: &HashMap<String, Vec<u8>>, path: &str) -> Result<T, String> {
+    let content = data
+        .get(path)
+        .ok_or(format!("{} is missing from the archive", path))?;
+    serde_json::from_slice(content).map_err(|e| format!("Invalid {}: {}", path, e))
+}
+
+async fn insert_rows<A>(txn: &DatabaseTransaction, rows: Vec<<A::Entity as EntityTrait>::Model>) -> Result<(), String>
+where
+    A: ActiveModelTrait + Send,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    for chunk in rows.chunks(500) {
+        A::Entity::insert_many(chunk.iter().cloned().map(IntoActiveModel::into_active_model))
+            .exec_without_returning(txn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Load an archive made by `export_archive` into an empty database
+///
+/// The archive is checked completely before anything is written. Rows are inserted in a single
+/// transaction, then texture files are saved in the texture storage and signing keys are extracted.
+/// The config is only restored when asked, since it holds settings like the database address that
+/// usually differ on a new server.
+pub async fn restore_archive(path: &Path, with_config: bool) -> Result<BackupReport, String> {
+    let mut data: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut files = 0;
+    for entry in open_archive(path)?.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+        if entry_path == MANIFEST || entry_path.starts_with("data/") {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+            data.insert(entry_path, content);
+        } else if entry.header().entry_type().is_file() {
+            files += 1;
+        }
+    }
+
+    let manifest: Manifest = parse(&data, MANIFEST)?;
+    if manifest.version != ARCHIVE_VERSION {
+        return Err(format!("Unsupported archive version {}.", manifest.version));
+    }
+    let migrations: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
+    if manifest.migrations != migrations {
+        return Err("The archive was exported by a different version of the server, \
+            migrate both databases to the same version first."
+            .to_string());
+    }
+    let users: Vec<user::Model> = parse(&data, &data_path("user"))?;
+    let profiles: Vec<crate::model::generated::profile::Model> = parse(&data, &data_path("profile"))?;
+    let textures: Vec<crate::model::generated::texture::Model> = parse(&data, &data_path("texture"))?;
+    let invites: Vec<crate::model::generated::invite::Model> = parse(&data, &data_path("invite"))?;
+
+    for count in [
+        User::find().count(&*DATABASE).await,
+        Profile::find().count(&*DATABASE).await,
+        Texture::find().count(&*DATABASE).await,
+        Invite::find().count(&*DATABASE).await,
+    ] {
+        if count.map_err(|e| e.to_string())? > 0 {
+            return Err("The database is not empty.".to_string());
+        }
+    }
+
+    let report = BackupReport {
+        users: users.len(),
+        profiles: profiles.len(),
+        textures: textures.len(),
+        invites: invites.len(),
+        files,
+    };
+    // Rows are inserted after the rows they reference, the default profiles are selected last
+    let selected_profiles: Vec<(String, String)> = users
+        .iter()
+        .filter_map(|user| Some((user.id.clone(), user.profile_id.clone()?)))
+        .collect();
+    let users = users
+        .into_iter()
+        .map(|user| user::Model { profile_id: None, ..user })
+        .collect();
+    let txn = DATABASE.begin().await.map_err(|e| e.to_string())?;
+    insert_rows::<user::ActiveModel>(&txn, users).await?;
+    insert_rows::<texture::ActiveModel>(&txn, textures).await?;
+    insert_rows::<profile::ActiveModel>(&txn, profiles).await?;
+    for (user_id, profile_id) in selected_profiles {
+        User::update_many()
+            .col_expr(user::Column::ProfileId, Expr::value(profile_id))
+            .filter(user::Column::Id.eq(user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    insert_rows::<invite::ActiveModel>(&txn, invites).await?;
+    txn.commit().await.map_err(|e| e.to_string())?;
+
+    let storage = texture_storage();
+    for entry in open_archive(path)?.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+        let dir = entry_path.split('/').next().unwrap_or_default();
+        if dir == TEXTURE_DIR && entry.header().entry_type().is_file() {
+            let id = entry_path.rsplit('/').next().unwrap_or_default().to_string();
+            if id.len() < 2 {
+                continue;
+            }
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+            storage.put(&id, content).await?;
+        } else if FILE_DIRS.contains(&dir) || (with_config && dir == CONFIG_DIR) {
+            // Refuses paths escaping the working directory
+            entry.unpack_in(".").map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,134 @@
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use chrono::{SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use moka::future::Cache;
+use rand::thread_rng;
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::model::serialized::certificate::{KeyPair, PlayerCertificate, PublicKey, PublicKeys};
+use crate::service::crypto::{sign_with, PROFILE_KEY_PAIR};
+use crate::service::keyring::published_public_keys;
+
+const KEY_BITS: usize = 2048;
+const KEY_VALID_HOURS: i64 = 48;
+const KEY_REFRESH_HOURS: i64 = 36;
+
+lazy_static! {
+    // The same certificate is handed out until the client is expected to refresh it
+    static ref CERTIFICATE_CACHE: Cache<String, PlayerCertificate> = Cache::builder()
+        .time_to_live(chrono::Duration::hours(KEY_REFRESH_HOURS).to_std().unwrap())
+        .build();
+}
+
+/// Get the chat signing certificate of a profile, generating a new key pair if needed
+///
+/// # Arguments
+///
+/// * `profile_id`: The id of the profile, without dashes
+///
+/// returns: PlayerCertificate
+pub async fn get_player_certificate(profile_id: &str) -> PlayerCertificate {
+    let profile_id = profile_id.to_string();
+    CERTIFICATE_CACHE
+        .get_with(profile_id.clone(), async move {
+            tokio::task::spawn_blocking(move || generate_player_certificate(&profile_id))
+                .await
+                .unwrap()
+        })
+        .await
+}
+
+/// Public keys used by game servers to verify profile properties and player certificates
+pub fn get_public_keys() -> PublicKeys {
+    PublicKeys {
+        profile_property_keys: published_public_keys()
+            .iter()
+            .map(|public_key| PublicKey {
+                public_key: encode_public_key(public_key),
+            })
+            .collect(),
+        player_certificate_keys: vec![PublicKey {
+            public_key: encode_public_key(&PROFILE_KEY_PAIR.1),
+        }],
+    }
+}
+
+fn encode_public_key(public_key: &RsaPublicKey) -> String {
+    BASE64_STANDARD.encode(public_key.to_public_key_der().unwrap())
+}
+
+fn generate_player_certificate(profile_id: &str) -> PlayerCertificate {
+    debug!("Generating certificate for profile {}.", profile_id);
+    let mut rng = thread_rng();
+    let private_key = RsaPrivateKey::new(&mut rng, KEY_BITS).expect("failed to generate a key");
+    let public_key = RsaPublicKey::from(&private_key);
+    let private_key = private_key.to_pkcs8_der().unwrap();
+    let public_key = public_key.to_public_key_der().unwrap();
+
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::hours(KEY_VALID_HOURS);
+    let refreshed_after = now + chrono::Duration::hours(KEY_REFRESH_HOURS);
+
+    // V1: expiry in milliseconds followed by the public key in pem format
+    let signature_v1 = format!(
+        "{}{}",
+        expires_at.timestamp_millis(),
+        to_pem(public_key.as_ref(), "RSA PUBLIC KEY")
+    );
+    // V2: profile uuid, expiry in milliseconds and the public key in der format
+    let mut signature_v2 = Vec::new();
+    signature_v2.extend_from_slice(Uuid::parse_str(profile_id).unwrap().as_bytes());
+    signature_v2.extend_from_slice(&expires_at.timestamp_millis().to_be_bytes());
+    signature_v2.extend_from_slice(public_key.as_ref());
+
+    PlayerCertificate {
+        key_pair: KeyPair {
+            private_key: to_pem(private_key.as_ref(), "RSA PRIVATE KEY"),
+            public_key: to_pem(public_key.as_ref(), "RSA PUBLIC KEY"),
+        },
+        public_key_signature: BASE64_STANDARD
+            .encode(sign_with(&PROFILE_KEY_PAIR.0, signature_v1.as_bytes())),
+        public_key_signature_v2: BASE64_STANDARD
+            .encode(sign_with(&PROFILE_KEY_PAIR.0, &signature_v2)),
+        expires_at: expires_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        refreshed_after: refreshed_after.to_rfc3339_opts(SecondsFormat::Millis, true),
+    }
+}
+
+/// Wrap der encoded key in the pem format used by Mojang, base64 split in lines of 76 characters
+///
+/// The lines are joined with `\r\n` like the MIME encoder of Java, the v1 signature covers these bytes.
+fn to_pem(der: &[u8], label: &str) -> String {
+    let encoded = BASE64_STANDARD.encode(der);
+    let lines = encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|line| String::from_utf8_lossy(line).to_string())
+        .collect::<Vec<String>>();
+
+    format!(
+        "-----BEGIN {}-----\n{}\n-----END {}-----\n",
+        label,
+        lines.join("\r\n"),
+        label
+    )
+}
+
+#[test]
+fn test_to_pem() {
+    // 60 bytes are 80 characters of base64, split after 76
+    let pem = to_pem(&[0; 60], "RSA PUBLIC KEY");
+
+    assert_eq!(
+        pem,
+        format!(
+            "-----BEGIN RSA PUBLIC KEY-----\n{}\r\n{}\n-----END RSA PUBLIC KEY-----\n",
+            "A".repeat(76),
+            "A".repeat(4)
+        )
+    );
+}
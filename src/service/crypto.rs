@@ -1,5 +1,4 @@
 use std::fs;
-use std::io::Read;
 
 use base64::Engine;
 use lazy_static::lazy_static;
@@ -12,11 +11,14 @@ use rsa::pkcs1::{
 use rsa::pkcs8::LineEnding;
 use sha1::{Digest, Sha1};
 
+use crate::service::keyring;
+
 lazy_static! {
-    pub static ref SIGNATURE_KEY_PAIR: (RsaPrivateKey, RsaPublicKey) = get_key_pair();
+    // Dedicated key signing the public keys of player certificates
+    pub static ref PROFILE_KEY_PAIR: (RsaPrivateKey, RsaPublicKey) = get_key_pair("keys/profile_private.pem");
 }
 
-fn generate_key_pair() -> (RsaPrivateKey, RsaPublicKey) {
+fn generate_key_pair(path: &str) -> (RsaPrivateKey, RsaPublicKey) {
     debug!("Generating new key pair.");
     fs::create_dir_all("keys").unwrap();
     let mut rng = thread_rng();
@@ -25,35 +27,51 @@ fn generate_key_pair() -> (RsaPrivateKey, RsaPublicKey) {
     let pub_key = RsaPublicKey::from(&priv_key);
     debug!("New key pair generated.");
     priv_key
-        .write_pkcs1_pem_file("keys/private.pem", LineEnding::LF)
+        .write_pkcs1_pem_file(path, LineEnding::LF)
         .unwrap();
 
     (priv_key, pub_key)
 }
 
-pub fn get_key_pair() -> (RsaPrivateKey, RsaPublicKey) {
-    if fs::metadata("keys/private.pem").is_ok() {
-        let mut private_key = String::new();
-
-        fs::File::open("keys/private.pem")
-            .unwrap()
-            .read_to_string(&mut private_key)
-            .unwrap();
-
-        let private_key = RsaPrivateKey::from_pkcs1_pem(&private_key).unwrap();
-        let public_key = RsaPublicKey::from(&private_key);
-        (private_key, public_key)
+pub fn get_key_pair(path: &str) -> (RsaPrivateKey, RsaPublicKey) {
+    if fs::metadata(path).is_ok() {
+        load_key_pair(path).unwrap()
     } else {
-        generate_key_pair()
+        generate_key_pair(path)
     }
 }
 
-pub fn rsa_sign(data: &[u8]) -> String {
+/// Read an existing key pair, nothing is generated when the file is missing
+pub fn load_key_pair(path: &str) -> Result<(RsaPrivateKey, RsaPublicKey), String> {
+    let private_key = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let private_key =
+        RsaPrivateKey::from_pkcs1_pem(&private_key).map_err(|e| format!("Invalid key {}: {}", path, e))?;
+    let public_key = RsaPublicKey::from(&private_key);
+
+    Ok((private_key, public_key))
+}
+
+/// Sign the data with SHA1withRSA
+pub fn sign_with(private_key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
     let mut hasher = Sha1::new();
     hasher.update(data);
     let hashed_data = hasher.finalize();
     let hash = hashed_data.as_slice();
 
-    let signature = SIGNATURE_KEY_PAIR.0.sign(PaddingScheme::PKCS1v15Sign {hash: Option::from(Hash::SHA1) }, hash).unwrap();
+    private_key.sign(PaddingScheme::PKCS1v15Sign {hash: Option::from(Hash::SHA1) }, hash).unwrap()
+}
+
+/// Sign the data with the active key of the keyring
+pub fn rsa_sign(data: &[u8]) -> String {
+    let signature = keyring::sign(data);
     base64::engine::general_purpose::STANDARD.encode(&signature)
 }
+
+#[test]
+fn test_load_key_pair_does_not_generate() {
+    let path = std::env::temp_dir().join(format!("missing-key-{}.pem", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    assert!(load_key_pair(path).is_err());
+    assert!(fs::metadata(path).is_err());
+}
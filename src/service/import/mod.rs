@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, NotSet, TransactionTrait};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::model::generated::prelude::Texture;
+use crate::model::generated::{profile, texture, user};
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::password::import_password_hash;
+use crate::service::profile::is_name_taken;
+use crate::service::texture::{describe_file, import_file, texture_type_of, update_ref_counts, StoredFile};
+use crate::service::user::{is_email_taken, is_valid_email};
+use crate::DATABASE;
+
+pub mod sql;
+
+pub type Row = Map<String, Value>;
+
+/// Rows of every table of another server, by table name
+#[derive(Default, Debug)]
+pub struct Dump {
+    pub tables: HashMap<String, Vec<Row>>,
+}
+
+impl Dump {
+    fn table(&self, prefix: &str, name: &str) -> &[Row] {
+        self.tables
+            .get(&format!("{}{}", prefix, name))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// Read a JSON export, either an object of table names to rows or the export format of phpMyAdmin
+pub fn parse_json_export(json: &str) -> Result<Dump, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let rows = |value: &Value| -> Vec<Row> {
+        value
+            .as_array()
+            .map(|rows| rows.iter().filter_map(|row| row.as_object().cloned()).collect())
+            .unwrap_or_default()
+    };
+
+    let mut dump = Dump::default();
+    match value {
+        Value::Object(tables) => {
+            for (name, table) in &tables {
+                dump.tables.insert(name.clone(), rows(table));
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter().filter(|item| item["type"] == "table") {
+                let Some(name) = item["name"].as_str() else {
+                    continue;
+                };
+                dump.tables.insert(name.to_string(), rows(&item["data"]));
+            }
+        }
+        _ => return Err("Expected an object or an array".to_string()),
+    }
+
+    Ok(dump)
+}
+
+pub struct ImportOptions {
+    /// Prefix of the table names, `DB_PREFIX` of Blessing Skin
+    pub table_prefix: String,
+    /// Lowercase `PWD_METHOD` of Blessing Skin
+    pub cipher: String,
+    /// `SALT` of Blessing Skin
+    pub salt: String,
+    /// `storage/textures` of Blessing Skin, textures are expected to be copied already if None
+    pub texture_dir: Option<PathBuf>,
+}
+
+#[derive(Default, Debug)]
+pub struct ImportReport {
+    pub users: u64,
+    pub profiles: u64,
+    pub textures: u64,
+    pub skipped: Vec<String>,
+}
+
+fn text(row: &Row, column: &str) -> Option<String> {
+    match row.get(column)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn int(row: &Row, column: &str) -> Option<i64> {
+    text(row, column)?.trim().parse().ok()
+}
+
+fn time(row: &Row, column: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(&text(row, column)?, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// Import the users, players and textures of a Blessing Skin database with the yggdrasil-api plugin
+///
+/// Player UUIDs of the plugin and texture hashes are kept, so signed textures and
+/// server data stay valid. Users whose email is taken and players whose name is taken are skipped.
+/// The first player of a user becomes its default profile.
+/// Every row is inserted in a single transaction, nothing is imported if any insert fails. Texture files
+/// are stored after the transaction is committed.
+pub async fn import_blessing_skin(dump: &Dump, options: &ImportOptions) -> Result<ImportReport, String> {
+    let prefix = options.table_prefix.as_str();
+    let mut report = ImportReport::default();
+
+    // tid -> (hash, type, file if read from the texture directory)
+    let mut textures: HashMap<i64, (String, String, Option<StoredFile>)> = HashMap::new();
+    for row in dump.table(prefix, "textures") {
+        let (Some(tid), Some(hash), Some(texture_type)) = (int(row, "tid"), text(row, "hash"), text(row, "type")) else {
+            continue;
+        };
+        if hash.len() < 2 || !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+            report.skipped.push(format!("texture {}: invalid hash {}", tid, hash));
+            continue;
+        }
+        let mut file = None;
+        if let Some(texture_dir) = &options.texture_dir {
+            file = match tokio::fs::read(texture_dir.join(&hash)).await {
+                Ok(content) => describe_file(&hash, &content),
+                Err(_) => None,
+            };
+            if file.is_none() {
+                report.skipped.push(format!("texture {}: missing or invalid file {}", tid, hash));
+                continue;
+            }
+        }
+        textures.insert(tid, (hash, texture_type, file));
+    }
+
+    // Lowercase player name -> uuid, assigned by the yggdrasil-api plugin
+    let uuids: HashMap<String, String> = dump
+        .table(prefix, "uuid")
+        .iter()
+        .filter_map(|row| {
+            let name = text(row, "name")?.to_lowercase();
+            let uuid = text(row, "uuid")?.replace('-', "").to_lowercase();
+            let is_valid = uuid.len() == 32 && uuid.chars().all(|c| c.is_ascii_hexdigit());
+            is_valid.then_some((name, uuid))
+        })
+        .collect();
+
+    let mut players: HashMap<i64, Vec<&Row>> = HashMap::new();
+    for row in dump.table(prefix, "players") {
+        if let Some(uid) = int(row, "uid") {
+            players.entry(uid).or_default().push(row);
+        }
+    }
+
+    let txn = DATABASE.begin().await.map_err(|e| e.to_string())?;
+    let mut imported_textures = HashSet::new();
+    let mut imported_names = HashSet::new();
+
+    for row in dump.table(prefix, "users") {
+        let uid = int(row, "uid").unwrap_or_default();
+        let Some(email) = text(row, "email").filter(|email| is_valid_email(email)) else {
+            report.skipped.push(format!("user {}: invalid email", uid));
+            continue;
+        };
+        if is_email_taken(&txn, &email).await {
+            report.skipped.push(format!("user {}: email {} is already used", uid, email));
+            continue;
+        }
+        let Some(password) = text(row, "password")
+            .and_then(|password| import_password_hash(&options.cipher, &options.salt, &password))
+        else {
+            report.skipped.push(format!("user {}: unsupported password hash", uid));
+            continue;
+        };
+
+        let now = Utc::now().naive_utc();
+        let user = user::ActiveModel {
+            id: Set(UuidNoChar::new().to_string()),
+            username: Set(None),
+            email: Set(email),
+            password: Set(password),
+            profile_id: Set(None),
+            preferred_language: Set(None),
+            create_time: Set(time(row, "register_at").unwrap_or(now)),
+            update_time: Set(now),
+            invite_code: Set(None),
+            verified: Set(int(row, "verified") != Some(0)),
+        }
+        .insert(&txn)
+        .await
+        .map_err(|e| e.to_string())?;
+        report.users += 1;
+
+        let mut profile_ids = Vec::new();
+        for player in players.get(&uid).map(Vec::as_slice).unwrap_or(&[]) {
+            let Some(name) = text(player, "name") else {
+                continue;
+            };
+            if imported_names.contains(&name.to_lowercase()) || is_name_taken(&txn, &name).await {
+                report.skipped.push(format!("player {}: name is already taken", name));
+                continue;
+            }
+
+            let texture = |column: &str| int(player, column).and_then(|tid| textures.get(&tid));
+            let skin = texture("tid_skin");
+            let cape = texture("tid_cape");
+            for (hash, texture_type, file) in skin.iter().chain(cape.iter()) {
+                if !imported_textures.insert(hash.clone()) {
+                    continue;
+                }
+                if Texture::find_by_id(hash.as_str()).one(&txn).await.map_err(|e| e.to_string())?.is_some() {
+                    continue;
+                }
+                let model = match texture_type.as_str() {
+                    "alex" => "slim",
+                    "cape" => "cape",
+                    _ => "default",
+                };
+                // The first user wearing the texture stands for the uploader
+                texture::ActiveModel {
+                    id: Set(hash.clone()),
+                    model: Set(model.to_string()),
+                    upload_time: NotSet,
+                    uploader_id: Set(Some(user.id.clone())),
+                    texture_type: Set(texture_type_of(model).to_string()),
+                    width: Set(file.as_ref().map(|file| file.width as i32)),
+                    height: Set(file.as_ref().map(|file| file.height as i32)),
+                    size: Set(file.as_ref().map(|file| file.size as i64)),
+                    ref_count: NotSet,
+                }
+                .insert(&txn)
+                .await
+                .map_err(|e| e.to_string())?;
+                report.textures += 1;
+            }
+
+            let profile_id = uuids
+                .get(&name.to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| UuidNoChar::new().to_string());
+            let model = match skin {
+                Some((_, texture_type, _)) if texture_type == "alex" => "slim",
+                _ => "default",
+            };
+            profile::ActiveModel {
+                id: Set(profile_id.clone()),
+                name: Set(name.clone()),
+                model: Set(model.to_string()),
+                owner_id: Set(user.id.clone()),
+                skin_texture: Set(skin.map(|(hash, _, _)| hash.clone())),
+                cape_texture: Set(cape.map(|(hash, _, _)| hash.clone())),
+                create_time: NotSet,
+                update_time: NotSet,
+            }
+            .insert(&txn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+            imported_names.insert(name.to_lowercase());
+            profile_ids.push(profile_id);
+            report.profiles += 1;
+        }
+
+        if let Some(profile_id) = profile_ids.first() {
+            let mut user = user.into_active_model();
+            user.profile_id = Set(Some(profile_id.clone()));
+            user.update(&txn).await.map_err(|e| e.to_string())?;
+        }
+    }
+
+    update_ref_counts(&txn, imported_textures.clone()).await;
+    txn.commit().await.map_err(|e| e.to_string())?;
+
+    // Files are stored once their records are, a failed import leaves nothing behind and can be run again
+    if let Some(texture_dir) = &options.texture_dir {
+        for hash in &imported_textures {
+            let saved = match tokio::fs::read(texture_dir.join(hash)).await {
+                Ok(content) => import_file(hash, content).await.is_some(),
+                Err(_) => false,
+            };
+            if !saved {
+                report.skipped.push(format!("texture {}: file could not be stored", hash));
+            }
+        }
+    }
+    for skipped in &report.skipped {
+        warn!("Skipped {}", skipped);
+    }
+
+    Ok(report)
+}
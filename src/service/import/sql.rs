@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::service::import::Dump;
+
+/// Read the rows of a MySQL dump, as written by `mysqldump` or phpMyAdmin
+///
+/// Only `CREATE TABLE` and `INSERT INTO` statements are understood, everything else is skipped.
+/// Column names of inserts without a column list are taken from the `CREATE TABLE` statement.
+pub fn parse_sql_dump(sql: &str) -> Result<Dump, String> {
+    let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+    let mut dump = Dump::default();
+
+    for statement in split_statements(sql) {
+        let mut parser = Parser::new(&statement);
+        if parser.keywords(&["CREATE", "TABLE"]) {
+            parser.keywords(&["IF", "NOT", "EXISTS"]);
+            let table = parser.identifier()?;
+            columns.insert(table, parser.column_definitions()?);
+        } else if parser.keywords(&["INSERT", "INTO"]) || parser.keywords(&["INSERT", "IGNORE", "INTO"]) {
+            let table = parser.identifier()?;
+            let names = if parser.peek() == Some('(') {
+                parser.identifier_list()?
+            } else {
+                columns
+                    .get(&table)
+                    .cloned()
+                    .ok_or(format!("Insert into {} before its creation", table))?
+            };
+            if !parser.keywords(&["VALUES"]) {
+                return Err(format!("Expected VALUES in insert into {}", table));
+            }
+
+            let rows = dump.tables.entry(table.clone()).or_default();
+            loop {
+                let values = parser.value_list()?;
+                if values.len() != names.len() {
+                    return Err(format!(
+                        "Insert into {} has {} values for {} columns",
+                        table,
+                        values.len(),
+                        names.len()
+                    ));
+                }
+                rows.push(names.iter().cloned().zip(values).collect::<Map<String, Value>>());
+
+                if !parser.eat(',') {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Split the dump on semicolons outside of quotes, dropping comments
+fn split_statements(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut statement = String::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(q) = quote {
+            statement.push(c);
+            if c == '\\' && q != '`' && i + 1 < chars.len() {
+                statement.push(chars[i + 1]);
+                i += 1;
+            } else if c == q {
+                quote = None;
+            }
+        } else if (c == '-' && chars.get(i + 1) == Some(&'-')) || c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else if c == ';' {
+            if !statement.trim().is_empty() {
+                statements.push(statement.trim().to_string());
+            }
+            statement.clear();
+        } else {
+            if c == '\'' || c == '"' || c == '`' {
+                quote = Some(c);
+            }
+            statement.push(c);
+        }
+        i += 1;
+    }
+    if !statement.trim().is_empty() {
+        statements.push(statement.trim().to_string());
+    }
+
+    statements
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(statement: &str) -> Self {
+        Parser {
+            chars: statement.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if !self.eat(c) {
+            return Err(format!("Expected '{}' at position {}", c, self.pos));
+        }
+        Ok(())
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.chars.len()
+            && (self.chars[self.pos].is_alphanumeric() || self.chars[self.pos] == '_')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Consume the keywords if they all follow, case insensitively
+    fn keywords(&mut self, keywords: &[&str]) -> bool {
+        let start = self.pos;
+        for keyword in keywords {
+            if !self.word().eq_ignore_ascii_case(keyword) {
+                self.pos = start;
+                return false;
+            }
+        }
+        true
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        if self.eat('`') {
+            let start = self.pos;
+            while self.pos < self.chars.len() && self.chars[self.pos] != '`' {
+                self.pos += 1;
+            }
+            let identifier = self.chars[start..self.pos].iter().collect();
+            self.pos += 1;
+            // Qualified names like `database`.`table` keep the last part
+            if self.chars.get(self.pos) == Some(&'.') {
+                self.pos += 1;
+                return self.identifier();
+            }
+            return Ok(identifier);
+        }
+
+        let identifier = self.word();
+        if identifier.is_empty() {
+            return Err(format!("Expected an identifier at position {}", self.pos));
+        }
+        if self.chars.get(self.pos) == Some(&'.') {
+            self.pos += 1;
+            return self.identifier();
+        }
+        Ok(identifier)
+    }
+
+    fn identifier_list(&mut self) -> Result<Vec<String>, String> {
+        self.expect('(')?;
+        let mut identifiers = vec![self.identifier()?];
+        while self.eat(',') {
+            identifiers.push(self.identifier()?);
+        }
+        self.expect(')')?;
+
+        Ok(identifiers)
+    }
+
+    /// Names of the columns in a `CREATE TABLE` body, skipping keys and constraints
+    fn column_definitions(&mut self) -> Result<Vec<String>, String> {
+        self.expect('(')?;
+        let mut columns = Vec::new();
+        loop {
+            if self.peek() == Some('`') {
+                columns.push(self.identifier()?);
+            }
+            // Skip the rest of the definition, which may contain parentheses and strings
+            let mut depth = 0;
+            loop {
+                match self.chars.get(self.pos) {
+                    None => return Err("Unterminated CREATE TABLE".to_string()),
+                    Some('\'') | Some('"') => {
+                        self.string()?;
+                        continue;
+                    }
+                    Some('(') => depth += 1,
+                    Some(')') if depth == 0 => {
+                        self.pos += 1;
+                        return Ok(columns);
+                    }
+                    Some(')') => depth -= 1,
+                    Some(',') if depth == 0 => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => {}
+                }
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn value_list(&mut self) -> Result<Vec<Value>, String> {
+        self.expect('(')?;
+        let mut values = vec![self.value()?];
+        while self.eat(',') {
+            values.push(self.value()?);
+        }
+        self.expect(')')?;
+
+        Ok(values)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(Value::String(self.string()?)),
+            Some(_) => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && !matches!(self.chars[self.pos], ',' | ')') {
+                    self.pos += 1;
+                }
+                let raw: String = self.chars[start..self.pos].iter().collect();
+                let raw = raw.trim();
+                if raw.eq_ignore_ascii_case("NULL") {
+                    return Ok(Value::Null);
+                }
+                if let Ok(number) = raw.parse::<i64>() {
+                    return Ok(Value::from(number));
+                }
+                Ok(Value::String(raw.to_string()))
+            }
+            None => Err("Unterminated value list".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err("Unterminated string".to_string());
+            };
+            self.pos += 1;
+            if c == '\\' {
+                let Some(&escaped) = self.chars.get(self.pos) else {
+                    return Err("Unterminated string".to_string());
+                };
+                self.pos += 1;
+                string.push(match escaped {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    'Z' => '\x1a',
+                    other => other,
+                });
+            } else if c == quote {
+                // A doubled quote stands for the quote itself
+                if self.chars.get(self.pos) == Some(&quote) {
+                    string.push(quote);
+                    self.pos += 1;
+                } else {
+                    return Ok(string);
+                }
+            } else {
+                string.push(c);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_sql_dump() {
+    let sql = r#"
+-- MySQL dump
+/*!40101 SET NAMES utf8mb4 */;
+DROP TABLE IF EXISTS `users`;
+CREATE TABLE `users` (
+  `uid` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `email` varchar(100) NOT NULL DEFAULT '',
+  `nickname` varchar(50) NOT NULL DEFAULT '',
+  `password` varchar(255) NOT NULL,
+  PRIMARY KEY (`uid`),
+  UNIQUE KEY `users_email_unique` (`email`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+INSERT INTO `users` VALUES (1,'alice@example.com','Alice;\'s','$2y$10$abc'),(2,'bob@example.com','Bob',NULL);
+INSERT INTO `players` (`pid`, `uid`, `name`) VALUES (1, 1, 'Alice');
+"#;
+    let dump = parse_sql_dump(sql).unwrap();
+
+    let users = &dump.tables["users"];
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0]["uid"], Value::from(1));
+    assert_eq!(users[0]["nickname"], Value::from("Alice;'s"));
+    assert_eq!(users[0]["password"], Value::from("$2y$10$abc"));
+    assert_eq!(users[1]["password"], Value::Null);
+    assert_eq!(dump.tables["players"][0]["name"], Value::from("Alice"));
+}
@@ -0,0 +1,118 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::{Condition, Expr};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, NotSet, QueryFilter, QueryOrder};
+
+use crate::model::generated::invite;
+use crate::model::generated::prelude::{Invite, User};
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::profile::is_valid_name;
+use crate::DATABASE;
+
+/// Mint a new invite code
+///
+/// # Arguments
+///
+/// * `creator_id`: The user who invites, None if minted by an operator
+/// * `max_uses`: How many users can register with the code
+/// * `expire_time`: The code can not be used after this time, never expires if None
+/// * `profile_name`: The name of the first profile of the invited user, chosen by the user if None.
+///   Only a single use code can preassign a name, the name can only be taken once.
+///
+/// returns: Result<invite::Model, String>: Err if the arguments are invalid or the creator does not exist
+pub async fn create_invite(
+    creator_id: Option<String>,
+    max_uses: i32,
+    expire_time: Option<NaiveDateTime>,
+    profile_name: Option<String>,
+) -> Result<invite::Model, String> {
+    if max_uses < 1 {
+        return Err("An invite must have at least one use.".to_string());
+    }
+    if let Some(profile_name) = &profile_name {
+        if max_uses != 1 {
+            return Err("Only an invite with a single use can preassign a profile name.".to_string());
+        }
+        if !is_valid_name(profile_name) {
+            return Err(format!("Invalid profile name {}.", profile_name));
+        }
+    }
+    if let Some(creator_id) = &creator_id {
+        if User::find_by_id(creator_id).one(&*DATABASE).await.unwrap().is_none() {
+            return Err(format!("User {} does not exist.", creator_id));
+        }
+    }
+
+    let invite = invite::ActiveModel {
+        code: Set(UuidNoChar::new().to_string()),
+        creator_id: Set(creator_id),
+        profile_name: Set(profile_name),
+        max_uses: Set(max_uses),
+        used_count: Set(0),
+        expire_time: Set(expire_time),
+        create_time: NotSet,
+    }
+    .insert(&*DATABASE)
+    .await
+    .unwrap();
+
+    Ok(invite)
+}
+
+pub async fn list_invites() -> Vec<invite::Model> {
+    Invite::find()
+        .order_by_desc(invite::Column::CreateTime)
+        .all(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+pub async fn delete_invite(code: &str) -> bool {
+    Invite::delete_by_id(code)
+        .exec(&*DATABASE)
+        .await
+        .unwrap()
+        .rows_affected
+        > 0
+}
+
+fn usable() -> Condition {
+    Condition::all()
+        .add(Expr::col(invite::Column::UsedCount).lt(Expr::col(invite::Column::MaxUses)))
+        .add(
+            Condition::any()
+                .add(invite::Column::ExpireTime.is_null())
+                .add(invite::Column::ExpireTime.gt(Utc::now().naive_utc())),
+        )
+}
+
+/// Find an invite that can still be used, without consuming it
+pub async fn find_usable_invite(code: &str) -> Option<invite::Model> {
+    Invite::find()
+        .filter(invite::Column::Code.eq(code))
+        .filter(usable())
+        .one(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+/// Use the invite once
+///
+/// The check and the increment happen in a single statement, so concurrent registrations
+/// can never use a code more than `max_uses` times.
+///
+/// returns: bool: false if the invite does not exist, expired or is used up
+pub async fn consume_invite<C: ConnectionTrait>(db: &C, code: &str) -> bool {
+    let result = Invite::update_many()
+        .col_expr(
+            invite::Column::UsedCount,
+            Expr::col(invite::Column::UsedCount).add(1),
+        )
+        .filter(invite::Column::Code.eq(code))
+        .filter(usable())
+        .exec(db)
+        .await
+        .unwrap();
+
+    result.rows_affected == 1
+}
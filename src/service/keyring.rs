@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::sync::RwLock;
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::crypto::{get_key_pair, load_key_pair, sign_with};
+use crate::CORE_CONFIG;
+
+const KEYRING_FILE: &str = "keys/keyring.json";
+// The key used before the keyring existed, kept so already signed data stays verifiable
+const LEGACY_KEY_ID: &str = "private";
+
+lazy_static! {
+    static ref KEYRING: RwLock<Keyring> = RwLock::new(Keyring::load());
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyState {
+    /// Used for signing and published
+    Active,
+    /// Only published for verification
+    Published,
+    /// Neither used nor published
+    Retired,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEntry {
+    pub id: String,
+    pub state: KeyState,
+    pub create_time: i64,
+    /// Time in milliseconds after which a published key is retired
+    pub retire_time: Option<i64>,
+}
+
+#[derive(Debug)]
+pub enum KeyringError {
+    NotFound,
+    IsActive,
+    IsRetired,
+}
+
+impl Display for KeyringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyringError::NotFound => write!(f, "Key not found."),
+            KeyringError::IsActive => write!(f, "The active key can not be retired."),
+            KeyringError::IsRetired => write!(f, "A retired key can not be activated."),
+        }
+    }
+}
+
+struct Keyring {
+    entries: Vec<KeyEntry>,
+    keys: HashMap<String, (RsaPrivateKey, RsaPublicKey)>,
+}
+
+fn key_path(id: &str) -> String {
+    format!("keys/{}.pem", id)
+}
+
+impl Keyring {
+    fn load() -> Self {
+        let entries = match fs::read_to_string(KEYRING_FILE) {
+            Ok(content) => serde_json::from_str(&content)
+                .unwrap_or_else(|e| panic!("Cannot parse {}: {}", KEYRING_FILE, e)),
+            Err(_) => {
+                // Generated on a new server, kept otherwise
+                get_key_pair(&key_path(LEGACY_KEY_ID));
+                let entries = vec![KeyEntry {
+                    id: LEGACY_KEY_ID.to_string(),
+                    state: KeyState::Active,
+                    create_time: Utc::now().timestamp_millis(),
+                    retire_time: None,
+                }];
+                save_entries(&entries);
+                entries
+            }
+        };
+
+        let mut keyring = Keyring {
+            entries: vec![],
+            keys: HashMap::new(),
+        };
+        keyring
+            .set_entries(entries)
+            .unwrap_or_else(|e| panic!("Cannot load the keyring: {}", e));
+        keyring
+    }
+
+    /// Replace the entries and load the keys that are not loaded yet
+    ///
+    /// Keys of the keyring are never generated here, a missing one fails and nothing is changed.
+    fn set_entries(&mut self, entries: Vec<KeyEntry>) -> Result<(), String> {
+        let mut loaded = HashMap::new();
+        for entry in &entries {
+            if entry.state != KeyState::Retired && !self.keys.contains_key(&entry.id) {
+                loaded.insert(entry.id.clone(), load_key_pair(&key_path(&entry.id))?);
+            }
+        }
+        self.keys.extend(loaded);
+        self.keys.retain(|id, _| {
+            entries
+                .iter()
+                .any(|entry| &entry.id == id && entry.state != KeyState::Retired)
+        });
+        self.entries = entries;
+
+        Ok(())
+    }
+
+    fn active(&self) -> &(RsaPrivateKey, RsaPublicKey) {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.state == KeyState::Active)
+            .expect("No active signing key in the keyring");
+        &self.keys[&entry.id]
+    }
+
+    fn entry_mut(&mut self, id: &str) -> Result<&mut KeyEntry, KeyringError> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(KeyringError::NotFound)
+    }
+}
+
+fn save_entries(entries: &[KeyEntry]) {
+    fs::create_dir_all("keys").unwrap();
+    let temp_file = format!("{}.tmp", KEYRING_FILE);
+    fs::write(&temp_file, serde_json::to_string_pretty(entries).unwrap()).unwrap();
+    fs::rename(&temp_file, KEYRING_FILE).unwrap();
+}
+
+/// Sign the data with the active key
+pub fn sign(data: &[u8]) -> Vec<u8> {
+    let keyring = KEYRING.read().unwrap();
+    sign_with(&keyring.active().0, data)
+}
+
+pub fn active_public_key() -> RsaPublicKey {
+    KEYRING.read().unwrap().active().1.clone()
+}
+
+/// Every key that should be trusted by game servers, including the active one
+pub fn published_public_keys() -> Vec<RsaPublicKey> {
+    let keyring = KEYRING.read().unwrap();
+    keyring
+        .entries
+        .iter()
+        .filter(|entry| entry.state != KeyState::Retired)
+        .map(|entry| keyring.keys[&entry.id].1.clone())
+        .collect()
+}
+
+pub fn list_keys() -> Vec<KeyEntry> {
+    KEYRING.read().unwrap().entries.clone()
+}
+
+/// Generate a new key and publish it without using it for signing yet
+///
+/// This takes a few seconds, call it from a blocking context.
+pub fn generate_key() -> KeyEntry {
+    let id = format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d%H%M%S"),
+        &UuidNoChar::new().to_string()[..8]
+    );
+    // Generated before taking the lock so signing is not blocked meanwhile
+    let key_pair = get_key_pair(&key_path(&id));
+    let entry = KeyEntry {
+        id: id.clone(),
+        state: KeyState::Published,
+        create_time: Utc::now().timestamp_millis(),
+        retire_time: None,
+    };
+
+    let mut keyring = KEYRING.write().unwrap();
+    keyring.keys.insert(id, key_pair);
+    keyring.entries.push(entry.clone());
+    save_entries(&keyring.entries);
+    info!("Signing key {} generated.", entry.id);
+
+    entry
+}
+
+/// Use the key for signing, the previously active key stays published for `key_grace_days`
+pub fn activate_key(id: &str) -> Result<(), KeyringError> {
+    let mut keyring = KEYRING.write().unwrap();
+    let entry = keyring.entry_mut(id)?;
+    match entry.state {
+        KeyState::Active => return Ok(()),
+        KeyState::Retired => return Err(KeyringError::IsRetired),
+        KeyState::Published => {}
+    }
+    entry.state = KeyState::Active;
+    entry.retire_time = None;
+
+    let retire_time = (Utc::now() + chrono::Duration::days(CORE_CONFIG.key_grace_days)).timestamp_millis();
+    for entry in keyring.entries.iter_mut() {
+        if entry.state == KeyState::Active && entry.id != id {
+            entry.state = KeyState::Published;
+            entry.retire_time = Some(retire_time);
+        }
+    }
+    save_entries(&keyring.entries);
+    info!("Signing key {} activated.", id);
+
+    Ok(())
+}
+
+/// Stop publishing the key
+pub fn retire_key(id: &str) -> Result<(), KeyringError> {
+    let mut keyring = KEYRING.write().unwrap();
+    let entry = keyring.entry_mut(id)?;
+    if entry.state == KeyState::Active {
+        return Err(KeyringError::IsActive);
+    }
+    entry.state = KeyState::Retired;
+    keyring.keys.remove(id);
+    save_entries(&keyring.entries);
+    info!("Signing key {} retired.", id);
+
+    Ok(())
+}
+
+/// Generate a new key and use it for signing right away
+pub fn rotate_key() -> KeyEntry {
+    let entry = generate_key();
+    activate_key(&entry.id).unwrap();
+    entry
+}
+
+/// Pick up changes made by other processes and retire keys whose grace period is over
+pub fn refresh() {
+    let now = Utc::now().timestamp_millis();
+    let mut entries: Vec<KeyEntry> = match fs::read_to_string(KEYRING_FILE) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        },
+        Err(_) => return,
+    };
+
+    let mut changed = false;
+    for entry in entries.iter_mut() {
+        if entry.state == KeyState::Published && entry.retire_time.is_some_and(|time| time <= now) {
+            debug!("Grace period of signing key {} is over.", entry.id);
+            entry.state = KeyState::Retired;
+            changed = true;
+        }
+    }
+    if changed {
+        save_entries(&entries);
+    }
+
+    if let Err(e) = KEYRING.write().unwrap().set_entries(entries) {
+        error!("Keyring not refreshed: {}", e);
+    }
+}
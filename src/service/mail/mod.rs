@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::Message;
+
+use crate::config::mail::MailTransportType;
+use crate::service::mail::smtp::SmtpMailTransport;
+use crate::service::mail::spool::SpoolMailTransport;
+use crate::MAIL_CONFIG;
+
+pub mod smtp;
+pub mod spool;
+
+lazy_static! {
+    static ref MAIL_TRANSPORT: Box<dyn MailTransport> = match MAIL_CONFIG.transport {
+        MailTransportType::Smtp => Box::new(SmtpMailTransport::new(&MAIL_CONFIG)),
+        MailTransportType::Spool => Box::new(SpoolMailTransport::new(&MAIL_CONFIG.spool_dir, &MAIL_CONFIG.from)),
+    };
+}
+
+#[derive(Clone, Debug)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// The way mails leave the server
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), String>;
+}
+
+/// Send a plain text mail through the configured transport
+pub async fn send_mail(mail: &Mail) -> Result<(), String> {
+    MAIL_TRANSPORT.send(mail).await
+}
+
+fn build_message(from: &str, mail: &Mail) -> Result<Message, String> {
+    let from: Mailbox = from.parse().map_err(|e| format!("Invalid sender {}: {}", from, e))?;
+    let to: Mailbox = mail
+        .to
+        .parse()
+        .map_err(|e| format!("Invalid recipient {}: {}", mail.to, e))?;
+
+    Message::builder()
+        .from(from)
+        .to(to)
+        .subject(&mail.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(mail.body.clone())
+        .map_err(|e| e.to_string())
+}
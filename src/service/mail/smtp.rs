@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+use crate::config::mail::{MailConfig, SmtpEncryption};
+use crate::service::mail::{build_message, Mail, MailTransport};
+
+pub struct SmtpMailTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailTransport {
+    pub fn new(config: &MailConfig) -> Self {
+        let builder = match config.smtp_encryption {
+            SmtpEncryption::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host),
+            SmtpEncryption::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .unwrap_or_else(|e| panic!("Invalid smtp host {}: {}", config.smtp_host, e)),
+            SmtpEncryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)
+                .unwrap_or_else(|e| panic!("Invalid smtp host {}: {}", config.smtp_host, e)),
+        };
+        let mut builder = builder.port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+            ));
+        }
+
+        SmtpMailTransport {
+            transport: builder.build(),
+            from: config.from.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailTransport for SmtpMailTransport {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        let message = build_message(&self.from, mail)?;
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::info;
+
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::mail::{build_message, Mail, MailTransport};
+
+/// Write every mail as an `.eml` file instead of sending it, for testing and offline setups
+pub struct SpoolMailTransport {
+    dir: PathBuf,
+    from: String,
+}
+
+impl SpoolMailTransport {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> Self {
+        SpoolMailTransport {
+            dir: dir.into(),
+            from: from.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailTransport for SpoolMailTransport {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        let message = build_message(&self.from, mail)?;
+        let file_name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S"),
+            UuidNoChar::new()
+        );
+        let path = self.dir.join(file_name);
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, message.formatted())
+            .await
+            .map_err(|e| e.to_string())?;
+        info!("Mail to {} written to {}.", mail.to, path.display());
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_spool_mail_transport() {
+    let dir = std::env::temp_dir().join(format!("spool-{}", UuidNoChar::new()));
+    let transport = SpoolMailTransport::new(&dir, "Yggdrasil <noreply@localhost>");
+    let mail = Mail {
+        to: "alice@example.com".to_string(),
+        subject: "Hello".to_string(),
+        body: "Hello alice".to_string(),
+    };
+    transport.send(&mail).await.unwrap();
+
+    let files = std::fs::read_dir(&dir).unwrap().collect::<Vec<_>>();
+    assert_eq!(files.len(), 1);
+    let content = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+    assert!(content.contains("To: alice@example.com"));
+    assert!(content.contains("Subject: Hello"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,5 +1,19 @@
+pub mod backup;
+pub mod invite;
+pub mod mail;
 pub mod password;
+pub mod password_reset;
+pub mod profile;
 pub mod token;
+pub mod token_store;
 pub mod crypto;
+pub mod import;
+pub mod keyring;
+pub mod certificate;
 pub mod session;
-pub mod texture;
\ No newline at end of file
+pub mod signed_token;
+pub mod texture;
+pub mod texture_gc;
+pub mod texture_storage;
+pub mod user;
+pub mod verification;
\ No newline at end of file
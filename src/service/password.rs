@@ -1,55 +1,416 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params, Version};
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
+use lazy_static::lazy_static;
+use md5::Md5;
 use pbkdf2::pbkdf2_hmac_array;
-use rand::Rng;
-use sha2::Sha256;
+use pbkdf2::Pbkdf2;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::config::auth::{PasswordAlgorithm, PasswordHashConfig};
+use crate::model::generated::prelude::User;
+use crate::model::generated::user;
+use crate::{AUTH_CONFIG, DATABASE};
 
-const ITERATIONS: u32 = 100_000;
+// Parameters of the `salt$hash` format used before hashes carried their algorithm
+const LEGACY_ITERATIONS: u32 = 100_000;
 const KEY_LEN: usize = 32;
 
+// A well-formed legacy hash of no password, checked when the stored hash is not a legacy one
+const DUMMY_LEGACY_HASH: &str = "dummy-salt$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+lazy_static! {
+    // Checked against when the user does not exist, so that answer takes as long as a wrong password
+    static ref DUMMY_HASH: String = generate_password_hash("dummy password");
+}
+
+// Whether some users still have a legacy hash, their KDF then runs on every verification
+static LEGACY_HASHES: AtomicBool = AtomicBool::new(false);
+
+/// Prepare the verification of passwords, before the first login
+///
+/// The dummy hash is made now so the first unknown user is not slower, and the stored hashes are
+/// checked for the legacy format. Hashes are never stored in that format again, so it is only checked once.
+pub async fn init() {
+    lazy_static::initialize(&DUMMY_HASH);
+    let legacy = User::find()
+        .filter(user::Column::Password.not_like("$%"))
+        .count(&*DATABASE)
+        .await
+        .unwrap();
+    LEGACY_HASHES.store(legacy > 0, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+thread_local! {
+    static KDF_CALLS: std::cell::RefCell<Vec<&'static str>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+#[cfg(test)]
+fn record_kdf(kdf: &'static str) {
+    KDF_CALLS.with(|calls| calls.borrow_mut().push(kdf));
+}
+
+#[cfg(not(test))]
+fn record_kdf(_kdf: &'static str) {}
+
+/// Hash the password with the configured algorithm
 pub fn generate_password_hash(password: &str) -> String {
-    // 生成随机盐
-    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz\
-                            ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-                            0123456789-";
-    const SALT_LEN: usize = 16;
+    hash_password(password, &AUTH_CONFIG.password_hash)
+}
+
+/// Hash the password into a PHC string, or the modular crypt format for bcrypt
+pub fn hash_password(password: &str, config: &PasswordHashConfig) -> String {
+    match config.algorithm {
+        PasswordAlgorithm::Argon2id => {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2(config)
+                .hash_password(password.as_bytes(), &salt)
+                .unwrap()
+                .to_string()
+        }
+        PasswordAlgorithm::Bcrypt => bcrypt::hash(password, config.bcrypt_cost).unwrap(),
+        PasswordAlgorithm::Pbkdf2 => {
+            let salt = SaltString::generate(&mut OsRng);
+            let params = pbkdf2::Params {
+                rounds: config.pbkdf2_iterations,
+                output_length: KEY_LEN,
+            };
+            Pbkdf2
+                .hash_password_customized(
+                    password.as_bytes(),
+                    Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()),
+                    None,
+                    params,
+                    &salt,
+                )
+                .unwrap()
+                .to_string()
+        }
+    }
+}
 
-    let mut rng = rand::thread_rng();
+fn argon2(config: &PasswordHashConfig) -> Argon2<'static> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+    .unwrap();
+    Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+}
 
-    let salt: String = (0..SALT_LEN)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect();
+/// Check the password of a user that may not exist
+///
+/// # Arguments
+///
+/// * `password`: The password sent by the client
+/// * `password_hash`: The hash of the user, None if there is no such user
+///
+/// returns: bool: always false without a user, after the same amount of work
+pub fn verify_user_password(password: &str, password_hash: Option<&str>) -> bool {
+    verify_or_dummy(
+        password,
+        password_hash,
+        &DUMMY_HASH,
+        &AUTH_CONFIG.password_hash,
+        LEGACY_HASHES.load(Ordering::Relaxed),
+    )
+}
 
-    let hash = pbkdf2_hmac_array::<Sha256, KEY_LEN>(password.as_bytes(), salt.as_bytes(), ITERATIONS);
-    let hash = BASE64_STANDARD.encode(hash);
+/// Run the configured KDF, and the legacy one while legacy hashes are stored, whatever the user is
+///
+/// Otherwise the time of a wrong password would tell an unknown user from one with a legacy, bcrypt or
+/// imported hash. Hashes of other algorithms are checked after the dummy hash.
+fn verify_or_dummy(
+    password: &str,
+    password_hash: Option<&str>,
+    dummy_hash: &str,
+    config: &PasswordHashConfig,
+    legacy_hashes: bool,
+) -> bool {
+    if legacy_hashes && !password_hash.is_some_and(is_legacy) {
+        verify_legacy_password(password, DUMMY_LEGACY_HASH);
+    }
+    if !password_hash.is_some_and(|password_hash| uses_algorithm(password_hash, &config.algorithm)) {
+        verify_password(password, dummy_hash);
+    }
 
-    format!("{}${}", salt, hash)
+    match password_hash {
+        Some(password_hash) => verify_password(password, password_hash),
+        None => false,
+    }
+}
+
+/// Whether the hash is made with the algorithm, whatever its cost
+fn uses_algorithm(password_hash: &str, algorithm: &PasswordAlgorithm) -> bool {
+    match algorithm {
+        PasswordAlgorithm::Argon2id => password_hash.starts_with("$argon2"),
+        PasswordAlgorithm::Bcrypt => is_bcrypt(password_hash),
+        PasswordAlgorithm::Pbkdf2 => password_hash.starts_with("$pbkdf2"),
+    }
+}
+
+/// The `salt$hash` format, the only one not starting with `$`
+fn is_legacy(password_hash: &str) -> bool {
+    !password_hash.starts_with('$')
 }
 
+/// Check the password against a hash in any of the supported formats
 pub fn verify_password(password: &str, password_hash: &str) -> bool {
-    let parts: Vec<&str> = password_hash.split('$').collect();
-    if parts.len() != 2 {
+    if is_bcrypt(password_hash) {
+        record_kdf("bcrypt");
+        return bcrypt::verify(password, password_hash).unwrap_or(false);
+    }
+    if is_legacy(password_hash) {
+        return verify_legacy_password(password, password_hash);
+    }
+    if let Some(password_hash) = password_hash.strip_prefix(IMPORTED_PREFIX) {
+        return verify_imported_password(password, password_hash);
+    }
+
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
         return false;
+    };
+    // Parameters are read from the hash, so hashes made with older settings still verify
+    match parsed.algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => {
+            record_kdf("argon2");
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        }
+        "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512" => {
+            record_kdf("pbkdf2");
+            Pbkdf2
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        }
+        _ => false,
     }
+}
 
-    let salt = parts[0];
-    let hash = parts[1];
+fn verify_legacy_password(password: &str, password_hash: &str) -> bool {
+    let parsed = password_hash
+        .split_once('$')
+        .filter(|(_, hash)| !hash.contains('$'))
+        .and_then(|(salt, hash)| Some((salt, BASE64_STANDARD.decode(hash).ok()?)));
+    // A malformed hash takes as long as a wrong password
+    let (salt, hash) = parsed.clone().unwrap_or_default();
 
-    let hash_calc = pbkdf2_hmac_array::<Sha256, KEY_LEN>(password.as_bytes(), salt.as_bytes(), ITERATIONS).to_vec();
-    let hash_calc = BASE64_STANDARD.encode(&hash_calc);
+    record_kdf("pbkdf2-legacy");
+    let hash_calc = pbkdf2_hmac_array::<Sha256, KEY_LEN>(password.as_bytes(), salt.as_bytes(), LEGACY_ITERATIONS);
 
-    hash_calc == hash
+    parsed.is_some() && bool::from(hash_calc.as_slice().ct_eq(hash.as_slice()))
+}
+
+/// Convert a hash imported from another server into a format `verify_password` accepts
+///
+/// # Arguments
+///
+/// * `cipher`: The lowercase name of the cipher used by the other server, like `php_password_hash` or `salted2sha256`
+/// * `salt`: The global salt of the other server, only used by salted ciphers
+/// * `password_hash`: The hash stored by the other server
+///
+/// returns: Option<String>: None if the cipher is not supported or the hash does not match it
+pub fn import_password_hash(cipher: &str, salt: &str, password_hash: &str) -> Option<String> {
+    match cipher {
+        "php_password_hash" | "bcrypt" | "argon2i" | "argon2id" => {
+            (is_bcrypt(password_hash) || password_hash.starts_with("$argon2"))
+                .then(|| password_hash.to_string())
+        }
+        _ => {
+            let digest_len = imported_digest(cipher, "", salt)?.len();
+            let password_hash = password_hash.to_ascii_lowercase();
+            if password_hash.len() != digest_len || !password_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            Some(format!("{}{}${}${}", IMPORTED_PREFIX, cipher, password_hash, salt))
+        }
+    }
+}
+
+// Imported hashes are stored as `$imported-<cipher>$<hex digest>$<salt>`, the salt may contain `$`
+const IMPORTED_PREFIX: &str = "$imported-";
+
+fn verify_imported_password(password: &str, password_hash: &str) -> bool {
+    let mut parts = password_hash.splitn(3, '$');
+    let (Some(cipher), Some(digest), Some(salt)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+
+    record_kdf("imported");
+    let Some(digest_calc) = imported_digest(cipher, password, salt) else {
+        return false;
+    };
+
+    digest_calc.as_bytes().ct_eq(digest.as_bytes()).into()
+}
+
+/// Unsalted and salted digests used by Blessing Skin, `salted2*` being `hash(hash(password) + salt)`
+fn imported_digest(cipher: &str, password: &str, salt: &str) -> Option<String> {
+    fn hex<D: Digest>(data: &str) -> String {
+        D::digest(data.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    let digest = match cipher {
+        "md5" => hex::<Md5>(password),
+        "salted2md5" => hex::<Md5>(&(hex::<Md5>(password) + salt)),
+        "sha256" => hex::<Sha256>(password),
+        "salted2sha256" => hex::<Sha256>(&(hex::<Sha256>(password) + salt)),
+        "sha512" => hex::<Sha512>(password),
+        "salted2sha512" => hex::<Sha512>(&(hex::<Sha512>(password) + salt)),
+        _ => return None,
+    };
+
+    Some(digest)
+}
+
+fn is_bcrypt(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
+/// Whether the hash was not made with the configured algorithm and cost
+pub fn needs_rehash(password_hash: &str, config: &PasswordHashConfig) -> bool {
+    // Imported digests are replaced on the first login, whatever the configured algorithm is
+    if password_hash.starts_with(IMPORTED_PREFIX) {
+        return true;
+    }
+    if is_bcrypt(password_hash) {
+        let cost = password_hash.get(4..6).and_then(|cost| cost.parse::<u32>().ok());
+        return config.algorithm != PasswordAlgorithm::Bcrypt || cost != Some(config.bcrypt_cost);
+    }
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+
+    let param = |name: &str| parsed.params.get_decimal(name);
+    match config.algorithm {
+        PasswordAlgorithm::Argon2id => {
+            parsed.algorithm.as_str() != "argon2id"
+                || param("m") != Some(config.argon2_memory_kib)
+                || param("t") != Some(config.argon2_iterations)
+                || param("p") != Some(config.argon2_parallelism)
+        }
+        PasswordAlgorithm::Pbkdf2 => {
+            parsed.algorithm.as_str() != "pbkdf2-sha256" || param("i") != Some(config.pbkdf2_iterations)
+        }
+        PasswordAlgorithm::Bcrypt => true,
+    }
+}
+
+#[cfg(test)]
+fn test_config(algorithm: PasswordAlgorithm) -> PasswordHashConfig {
+    PasswordHashConfig {
+        algorithm,
+        argon2_memory_kib: 1024,
+        argon2_iterations: 1,
+        argon2_parallelism: 1,
+        bcrypt_cost: 4,
+        pbkdf2_iterations: 1000,
+    }
 }
 
 #[test]
 fn test_hash_password() {
     let password = "Test123";
-    let hash = generate_password_hash(password);
+    for algorithm in [PasswordAlgorithm::Argon2id, PasswordAlgorithm::Bcrypt, PasswordAlgorithm::Pbkdf2] {
+        let config = test_config(algorithm);
+        let hash = hash_password(password, &config);
+
+        println!("Password hash: {}", &hash);
+
+        assert!(verify_password(password, &hash));
+        assert!(!verify_password("Test124", &hash));
+        assert!(!needs_rehash(&hash, &config));
+    }
+}
+
+#[test]
+fn test_legacy_password() {
+    let hash = pbkdf2_hmac_array::<Sha256, KEY_LEN>(b"Test123", b"0123456789abcdef", LEGACY_ITERATIONS);
+    let hash = format!("0123456789abcdef${}", BASE64_STANDARD.encode(hash));
+
+    assert!(verify_password("Test123", &hash));
+    assert!(!verify_password("Test124", &hash));
+    assert!(needs_rehash(&hash, &test_config(PasswordAlgorithm::Argon2id)));
+}
+
+#[test]
+fn test_needs_rehash() {
+    let bcrypt = hash_password("Test123", &test_config(PasswordAlgorithm::Bcrypt));
+    let mut config = test_config(PasswordAlgorithm::Argon2id);
+    assert!(needs_rehash(&bcrypt, &config));
+
+    let argon2 = hash_password("Test123", &config);
+    config.argon2_iterations = 2;
+    assert!(needs_rehash(&argon2, &config));
+}
+
+#[test]
+fn test_unknown_user_uses_kdf() {
+    let config = test_config(PasswordAlgorithm::Argon2id);
+    let hash = hash_password("Test123", &config);
+    let dummy_hash = hash_password("dummy password", &config);
+    let legacy_hash = pbkdf2_hmac_array::<Sha256, KEY_LEN>(b"Test123", b"0123456789abcdef", LEGACY_ITERATIONS);
+    let legacy_hash = format!("0123456789abcdef${}", BASE64_STANDARD.encode(legacy_hash));
+    let bcrypt_hash = hash_password("Test123", &test_config(PasswordAlgorithm::Bcrypt));
+    let digest = format!("{:x}", Md5::digest(b"Test123"));
+    let imported_hash = import_password_hash("md5", "", &digest).unwrap();
+    let kdfs = |password_hash: Option<&str>, legacy_hashes: bool| {
+        KDF_CALLS.with(|calls| calls.borrow_mut().clear());
+        assert!(!verify_or_dummy("Test124", password_hash, &dummy_hash, &config, legacy_hashes));
+        let mut kdfs = KDF_CALLS.with(|calls| calls.take());
+        kdfs.sort();
+        kdfs
+    };
+
+    assert_eq!(kdfs(Some(&hash), false), vec!["argon2"]);
+    assert_eq!(kdfs(None, false), vec!["argon2"]);
+    assert_eq!(kdfs(Some(&bcrypt_hash), false), vec!["argon2", "bcrypt"]);
+    assert_eq!(kdfs(Some(&imported_hash), false), vec!["argon2", "imported"]);
+
+    let expected = vec!["argon2", "pbkdf2-legacy"];
+    assert_eq!(kdfs(Some(&hash), true), expected);
+    assert_eq!(kdfs(Some(&legacy_hash), true), expected);
+    assert_eq!(kdfs(Some("malformed$legacy$hash"), true), expected);
+    assert_eq!(kdfs(None, true), expected);
+
+    assert!(verify_or_dummy("Test123", Some(&legacy_hash), &dummy_hash, &config, true));
+    assert!(verify_or_dummy("Test123", Some(&bcrypt_hash), &dummy_hash, &config, false));
+    // Even the right password of the dummy hashes never logs in
+    assert!(!verify_or_dummy("dummy password", None, &dummy_hash, &config, true));
+    assert!(!verify_or_dummy("", None, &dummy_hash, &config, true));
+}
 
-    println!("Password hash: {}", &hash);
+#[test]
+fn test_imported_password() {
+    // md5(md5("Test123") + "salt")
+    let digest = format!("{:x}", Md5::digest(format!("{:x}", Md5::digest(b"Test123")) + "salt"));
+    let hash = import_password_hash("salted2md5", "salt", &digest).unwrap();
+
+    assert!(verify_password("Test123", &hash));
+    assert!(!verify_password("Test124", &hash));
+    for algorithm in [PasswordAlgorithm::Argon2id, PasswordAlgorithm::Bcrypt, PasswordAlgorithm::Pbkdf2] {
+        assert!(needs_rehash(&hash, &test_config(algorithm)));
+    }
 
-    assert!(verify_password(password, &hash))
-}
\ No newline at end of file
+    let bcrypt = hash_password("Test123", &test_config(PasswordAlgorithm::Bcrypt)).replacen("$2b$", "$2y$", 1);
+    assert_eq!(import_password_hash("php_password_hash", "", &bcrypt), Some(bcrypt.clone()));
+    assert!(verify_password("Test123", &bcrypt));
+
+    assert!(import_password_hash("sha256", "", "not a digest").is_none());
+    assert!(import_password_hash("unknown", "", &digest).is_none());
+}
@@ -0,0 +1,55 @@
+use sea_orm::EntityTrait;
+
+use crate::model::generated::prelude::User;
+use crate::model::generated::user;
+use crate::service::mail::{send_mail, Mail};
+use crate::service::signed_token::{parse_token, sign_token};
+use crate::service::user::reset_password;
+use crate::service::verification::set_verified;
+use crate::{AUTH_CONFIG, DATABASE, META_CONFIG};
+
+const PURPOSE: &str = "reset_password";
+
+/// Mail a password reset token to the user
+///
+/// The token is bound to the current password hash, so it can only be used once.
+pub async fn send_reset_mail(user: &user::Model) -> Result<(), String> {
+    let token = sign_token(
+        PURPOSE,
+        &user.id,
+        &user.password,
+        chrono::Duration::minutes(AUTH_CONFIG.reset_token_minutes),
+    );
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: format!("Reset your password for {}", META_CONFIG.server_name),
+        body: format!(
+            "Someone asked to reset the password of your account. If it was not you, ignore this mail.\n\n\
+            Use the token below to set a new password, it expires in {} minutes.\n\n{}\n",
+            AUTH_CONFIG.reset_token_minutes, token
+        ),
+    };
+
+    send_mail(&mail).await
+}
+
+/// Set a new password if the token is valid, all tokens of the user are revoked
+///
+/// returns: bool: false if the token is invalid, expired or already used
+pub async fn reset_password_with_token(token: &str, password: &str) -> bool {
+    let Some(token) = parse_token(token) else {
+        return false;
+    };
+    let Some(user) = User::find_by_id(&token.user_id).one(&*DATABASE).await.unwrap() else {
+        return false;
+    };
+    if !token.verify(PURPOSE, &user.password) {
+        return false;
+    }
+
+    // Receiving the mail proves the ownership of the address
+    let user = set_verified(user, true).await;
+    reset_password(user, password).await;
+
+    true
+}
@@ -0,0 +1,149 @@
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, ModelTrait,
+    NotSet, PaginatorTrait, QueryFilter,
+};
+
+use crate::model::generated::prelude::{Profile, User};
+use crate::model::generated::{profile, user};
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::texture::{profile_textures, update_ref_counts};
+use crate::service::token::invalidate_profile_tokens;
+use crate::DATABASE;
+
+/// Check whether a profile name is acceptable by the game: 1 to 16 letters, digits or underscores
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 16
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Check whether a name is already used by any profile, ignoring case
+///
+/// The connection can be a transaction, which also sees the profiles it inserted.
+pub async fn is_name_taken<C: ConnectionTrait>(db: &C, name: &str) -> bool {
+    Profile::find().filter(name_is(name)).one(db).await.unwrap().is_some()
+}
+
+/// Find the profile with the name, ignoring case
+pub async fn find_profile_by_name(name: &str) -> Option<profile::Model> {
+    Profile::find().filter(name_is(name)).one(&*DATABASE).await.unwrap()
+}
+
+fn name_is(name: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(profile::Column::Name))).eq(name.to_lowercase())
+}
+
+/// Find the profiles matching any of the names, ignoring case
+pub async fn find_profiles_by_names(names: &[String]) -> Vec<profile::Model> {
+    if names.is_empty() {
+        return vec![];
+    }
+
+    let names = names.iter().map(|name| name.to_lowercase()).collect::<Vec<String>>();
+    Profile::find()
+        .filter(Expr::expr(Func::lower(Expr::col(profile::Column::Name))).is_in(names))
+        .all(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+pub async fn count_profiles(owner_id: &str) -> u64 {
+    Profile::find()
+        .filter(profile::Column::OwnerId.eq(owner_id))
+        .count(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+/// Get a profile only if it is owned by the user
+pub async fn get_owned_profile(profile_id: &str, owner_id: &str) -> Option<profile::Model> {
+    Profile::find()
+        .filter(profile::Column::Id.eq(profile_id))
+        .filter(profile::Column::OwnerId.eq(owner_id))
+        .one(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+/// Create a profile for the user
+///
+/// returns: Result<profile::Model, DbErr>, see `unique_conflict` if the name was taken meanwhile
+pub async fn create_profile(owner_id: &str, name: &str) -> Result<profile::Model, DbErr> {
+    profile::ActiveModel {
+        id: Set(UuidNoChar::new().to_string()),
+        name: Set(name.to_string()),
+        model: NotSet,
+        owner_id: Set(owner_id.to_string()),
+        skin_texture: NotSet,
+        cape_texture: NotSet,
+        create_time: NotSet,
+        update_time: NotSet,
+    }
+    .insert(&*DATABASE)
+    .await
+}
+
+/// Rename a profile
+///
+/// returns: Result<profile::Model, DbErr>, see `unique_conflict` if the name was taken meanwhile
+pub async fn rename_profile(profile: profile::Model, name: &str) -> Result<profile::Model, DbErr> {
+    let mut profile = profile.into_active_model();
+    profile.name = Set(name.to_string());
+    profile.update_time = Set(chrono::Utc::now().naive_utc());
+    profile.update(&*DATABASE).await
+}
+
+/// Delete a profile, revoking every token bound to it
+pub async fn delete_profile(profile: profile::Model) {
+    // Done first, the foreign key would clear the default profile of the owner
+    replace_default_profile(&profile.owner_id, &profile.id).await;
+    Profile::delete_by_id(&profile.id)
+        .exec(&*DATABASE)
+        .await
+        .unwrap();
+    update_ref_counts(&*DATABASE, profile_textures(std::slice::from_ref(&profile))).await;
+    invalidate_profile_tokens(&profile.owner_id, &profile.id).await;
+}
+
+/// Give a profile to another user, revoking every token of the previous owner bound to it
+pub async fn transfer_profile(profile: profile::Model, owner_id: &str) -> profile::Model {
+    let previous_owner = profile.owner_id.clone();
+    replace_default_profile(&previous_owner, &profile.id).await;
+    let mut profile = profile.into_active_model();
+    profile.owner_id = Set(owner_id.to_string());
+    profile.update_time = Set(chrono::Utc::now().naive_utc());
+    let profile = profile.update(&*DATABASE).await.unwrap();
+
+    invalidate_profile_tokens(&previous_owner, &profile.id).await;
+
+    profile
+}
+
+/// If the profile is the default profile of the user, use another profile of the user instead
+///
+/// Called before the profile leaves the user, so it is excluded from the candidates.
+async fn replace_default_profile(owner_id: &str, profile_id: &str) {
+    let owner = User::find()
+        .filter(user::Column::Id.eq(owner_id))
+        .filter(user::Column::ProfileId.eq(profile_id))
+        .one(&*DATABASE)
+        .await
+        .unwrap();
+    if let Some(owner) = owner {
+        let replacement = owner
+            .find_related(Profile)
+            .filter(profile::Column::Id.ne(profile_id))
+            .one(&*DATABASE)
+            .await
+            .unwrap()
+            .map(|profile| profile.id);
+
+        let mut owner = owner.into_active_model();
+        owner.profile_id = Set(replacement);
+        owner.update(&*DATABASE).await.unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs;
+
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use log::debug;
+use rand::Rng;
+use sha2::Sha256;
+
+const SECRET_FILE: &str = "keys/token_secret";
+
+lazy_static! {
+    static ref SECRET: Vec<u8> = get_secret();
+}
+
+fn get_secret() -> Vec<u8> {
+    if let Ok(secret) = fs::read(SECRET_FILE) {
+        return secret;
+    }
+
+    debug!("Generating new token secret.");
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill(&mut secret);
+    fs::create_dir_all("keys").unwrap();
+    fs::write(SECRET_FILE, secret).unwrap();
+    secret.to_vec()
+}
+
+/// A stateless token sent to users by mail, in the form of `user_id.expire_time.signature`
+///
+/// Besides the purpose, the signature covers a `binding` value read from the user when the token
+/// is checked, so a token stops working as soon as that value changes.
+pub struct SignedToken {
+    pub user_id: String,
+    expire_time: i64,
+    signature: Vec<u8>,
+}
+
+fn mac(purpose: &str, user_id: &str, expire_time: i64, binding: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&SECRET).unwrap();
+    for part in [purpose, user_id, &expire_time.to_string(), binding] {
+        mac.update(part.as_bytes());
+        mac.update(&[0]);
+    }
+    mac
+}
+
+/// Sign a new token
+///
+/// # Arguments
+///
+/// * `purpose`: What the token can be used for, a token is only valid for its own purpose
+/// * `user_id`: The user the token is issued to
+/// * `binding`: The value the token is bound to
+/// * `valid_for`: How long the token can be used
+///
+/// returns: String
+pub fn sign_token(purpose: &str, user_id: &str, binding: &str, valid_for: chrono::Duration) -> String {
+    let expire_time = (Utc::now() + valid_for).timestamp_millis();
+    let signature = mac(purpose, user_id, expire_time, binding)
+        .finalize()
+        .into_bytes();
+
+    format!(
+        "{}.{}.{}",
+        user_id,
+        expire_time,
+        BASE64_URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// Read a token without checking its signature
+///
+/// returns: Option<SignedToken>: None if the token is malformed or expired
+pub fn parse_token(token: &str) -> Option<SignedToken> {
+    let mut parts = token.splitn(3, '.');
+    let user_id = parts.next()?.to_string();
+    let expire_time: i64 = parts.next()?.parse().ok()?;
+    let signature = BASE64_URL_SAFE_NO_PAD.decode(parts.next()?).ok()?;
+
+    if expire_time <= Utc::now().timestamp_millis() {
+        return None;
+    }
+
+    Some(SignedToken {
+        user_id,
+        expire_time,
+        signature,
+    })
+}
+
+impl SignedToken {
+    /// Check the signature in constant time
+    pub fn verify(&self, purpose: &str, binding: &str) -> bool {
+        mac(purpose, &self.user_id, self.expire_time, binding)
+            .verify_slice(&self.signature)
+            .is_ok()
+    }
+}
+
+#[test]
+fn test_signed_token() {
+    let token = sign_token("test", "alice", "alice@example.com", chrono::Duration::hours(1));
+    let parsed = parse_token(&token).unwrap();
+
+    assert_eq!(parsed.user_id, "alice");
+    assert!(parsed.verify("test", "alice@example.com"));
+    assert!(!parsed.verify("test", "bob@example.com"));
+    assert!(!parsed.verify("other", "alice@example.com"));
+
+    let expired = sign_token("test", "alice", "alice@example.com", chrono::Duration::hours(-1));
+    assert!(parse_token(&expired).is_none());
+}
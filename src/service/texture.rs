@@ -1,35 +1,50 @@
+use std::collections::HashSet;
 use std::io::Cursor;
-use std::path::PathBuf;
 
 use base64::Engine;
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use image::{DynamicImage, EncodableLayout};
 use image::codecs::png::PngDecoder;
 use image::ImageFormat::Png;
-use image::io::{Limits, Reader as ImageReader};
+use image::io::Limits;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder,
+};
 use sha2::{Digest, Sha256};
-use tokio::fs::try_exists;
+use tracing::warn;
 
-use crate::TEXTURE_CONFIG;
+use crate::model::generated::prelude::{Profile, Texture};
+use crate::model::generated::{profile, texture};
+use crate::service::texture_storage::texture_storage;
+use crate::{DATABASE, TEXTURE_CONFIG};
 
-/// Write a file to disk and generate the id of the file
+/// A texture file saved in the storage
+#[derive(Clone, Debug)]
+pub struct StoredFile {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the stored file in bytes
+    pub size: usize,
+}
+
+/// Save a file in the texture storage and generate the id of the file
 ///
 /// # Arguments
 ///
 /// * `file_content`: The content of file
 ///
-/// returns: Option<String>: Some(id) if the file is saved successfully, None if error occurs
-pub async fn write_file(file_content: impl AsRef<[u8]>) -> Option<String> {
+/// returns: Option<StoredFile>: Some(file) if the file is saved successfully, None if error occurs
+pub async fn write_file(file_content: impl AsRef<[u8]>) -> Option<StoredFile> {
     let mut hasher = Sha256::new();
     hasher.update(file_content.as_ref());
     hasher.update(file_content.as_ref().len().to_le_bytes());
     let hasher = hasher.finalize();
 
     let id = BASE64_URL_SAFE_NO_PAD.encode(hasher.as_bytes()).to_string();
-
-    let mut path = PathBuf::from("./textures");
-    path.push(id[0..2].to_string().to_ascii_lowercase());
-    path.push(&id);
     
     // Recode the image
     let mut limit = Limits::default();
@@ -38,78 +53,235 @@ pub async fn write_file(file_content: impl AsRef<[u8]>) -> Option<String> {
     let image = PngDecoder::with_limits(Cursor::new(file_content), limit).unwrap();
     let image = DynamicImage::from_decoder(image).unwrap();
     
-    if image.width() % 64 != 0 || image.height() % 32 != 0 {
+    if !image.width().is_multiple_of(64) || !image.height().is_multiple_of(32) {
         return None;
     }
     
     let mut image_bytes: Vec<u8> = Vec::new();
     image.write_to(&mut Cursor::new(&mut image_bytes), Png).unwrap();
+    let file = StoredFile {
+        id,
+        width: image.width(),
+        height: image.height(),
+        size: image_bytes.len(),
+    };
 
-    if try_exists(&path).await.unwrap() {
-        return Some(id);
+    // An existing file is written again, the new modification time keeps the GC from deleting it before a
+    // profile uses it
+    if let Err(e) = texture_storage().put(&file.id, image_bytes).await {
+        warn!("Cannot save texture {}: {}", file.id, e);
+        return None;
     }
-    tokio::fs::create_dir_all(&path.parent().unwrap())
-        .await
-        .unwrap();
-    tokio::fs::write(&path, image_bytes)
-        .await
-        .unwrap();
-
-    Some(id)
+
+    Some(file)
 }
 
-/// Delete a file from disk
+/// Record an uploaded texture in the database, profiles can only use recorded textures
+///
+/// The same content uploaded again keeps its first record.
 ///
 /// # Arguments
 ///
-/// * `file_id`: The id of the file
+/// * `file`: The file, as returned by `write_file`
+/// * `model`: The model of the texture, `default`, `slim` or `cape`
+/// * `uploader_id`: The id of the uploading user
 ///
 /// returns: ()
-async fn delete_file(file_id: &str) {
-    let mut path = PathBuf::from("./textures");
-    path.push(file_id[0..2].to_string().to_ascii_lowercase());
-    path.push(file_id);
+pub async fn save_texture(file: &StoredFile, model: &str, uploader_id: &str) {
+    if Texture::find_by_id(&file.id).one(&*DATABASE).await.unwrap().is_some() {
+        return;
+    }
 
-    if try_exists(&path).await.unwrap() {
-        tokio::fs::remove_file(&path).await.unwrap();
+    texture::ActiveModel {
+        id: Set(file.id.clone()),
+        model: Set(model.to_string()),
+        upload_time: NotSet,
+        uploader_id: Set(Some(uploader_id.to_string())),
+        texture_type: Set(texture_type_of(model).to_string()),
+        width: Set(Some(file.width as i32)),
+        height: Set(Some(file.height as i32)),
+        size: Set(Some(file.size as i64)),
+        ref_count: NotSet,
     }
+    .insert(&*DATABASE)
+    .await
+    .unwrap();
 }
 
+/// The type of a texture, `skin` or `cape`, from its model
+pub fn texture_type_of(model: &str) -> &'static str {
+    if model == "cape" {
+        "cape"
+    } else {
+        "skin"
+    }
+}
 
-/// Read a file from disk
+/// Count again how many profiles use each of the textures
 ///
-/// # Arguments 
+/// Called after profiles change their textures, unknown ids are ignored.
 ///
-/// * `file_id`: The id of the file
+/// # Arguments
 ///
-/// returns: Option<DynamicImage>: Some(DynamicImage) if the file is read successfully, None if not exist or format not recognized
-///   
-/// # Examples 
+/// * `db`: The connection to use, can be a transaction
+/// * `texture_ids`: The textures to count
 ///
-/// ```
-/// let file = read_image("test").unwrap();
-/// ```
-pub async fn read_image(file_id: &str) -> Option<DynamicImage> {
-    let mut path = PathBuf::from("./textures");
-    path.push(file_id[0..2].to_string().to_ascii_lowercase());
-    path.push(file_id);
+/// returns: ()
+pub async fn update_ref_counts<C: ConnectionTrait>(db: &C, texture_ids: impl IntoIterator<Item = String>) {
+    for texture_id in texture_ids.into_iter().collect::<HashSet<String>>() {
+        let ref_count = Profile::find()
+            .filter(
+                Condition::any()
+                    .add(profile::Column::SkinTexture.eq(&texture_id))
+                    .add(profile::Column::CapeTexture.eq(&texture_id)),
+            )
+            .count(db)
+            .await
+            .unwrap();
+        Texture::update_many()
+            .col_expr(texture::Column::RefCount, Expr::value(ref_count as i32))
+            .filter(texture::Column::Id.eq(texture_id))
+            .exec(db)
+            .await
+            .unwrap();
+    }
+}
 
-    if !try_exists(&path).await.unwrap() {
-        return None;
+/// The textures of every profile, used to count references again after the profiles change
+pub fn profile_textures(profiles: &[profile::Model]) -> Vec<String> {
+    profiles
+        .iter()
+        .flat_map(|profile| [profile.skin_texture.clone(), profile.cape_texture.clone()])
+        .flatten()
+        .collect()
+}
+
+/// List the recorded textures, newest first
+///
+/// # Arguments
+///
+/// * `uploader_id`: Only list the textures uploaded by this user if given
+///
+/// returns: Vec<texture::Model>
+pub async fn list_textures(uploader_id: Option<&str>) -> Vec<texture::Model> {
+    let mut query = Texture::find().order_by_desc(texture::Column::UploadTime);
+    if let Some(uploader_id) = uploader_id {
+        query = query.filter(texture::Column::UploaderId.eq(uploader_id));
     }
 
-    let file = ImageReader::open(path);
-    if file.is_err() {
+    query.all(&*DATABASE).await.unwrap()
+}
+
+/// Save a file under an existing id, used to keep the ids of imported textures
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+/// * `file_content`: The content of file, must be a png image
+///
+/// returns: Option<StoredFile>: None if the content is not a png image
+pub async fn import_file(file_id: &str, file_content: impl AsRef<[u8]>) -> Option<StoredFile> {
+    let file = describe_file(file_id, file_content.as_ref())?;
+
+    if let Err(e) = texture_storage().put(file_id, file_content.as_ref().to_vec()).await {
+        warn!("Cannot save texture {}: {}", file_id, e);
         return None;
     }
-    let file = file.unwrap().with_guessed_format();
-    if file.is_err() {
+
+    Some(file)
+}
+
+/// Describe a file as `import_file` would store it, without storing it
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+/// * `file_content`: The content of file, must be a png image
+///
+/// returns: Option<StoredFile>: None if the content is not a png image
+pub fn describe_file(file_id: &str, file_content: &[u8]) -> Option<StoredFile> {
+    let image = image::load_from_memory_with_format(file_content, Png).ok()?;
+
+    Some(StoredFile {
+        id: file_id.to_string(),
+        width: image.width(),
+        height: image.height(),
+        size: file_content.len(),
+    })
+}
+
+/// Delete a file from the texture storage
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+///
+/// returns: Result<(), String>: Ok if the file is deleted or did not exist
+pub async fn delete_file(file_id: &str) -> Result<(), String> {
+    texture_storage().delete(file_id).await
+}
+
+/// Read a file from the texture storage, as it was stored
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+///
+/// returns: Option<Vec<u8>>: None if the file does not exist or can not be read
+pub async fn read_file(file_id: &str) -> Option<Vec<u8>> {
+    if !is_valid_id(file_id) {
         return None;
     }
-    let file = file.unwrap().decode();
-    if file.is_err() {
+
+    texture_storage().get(file_id).await.unwrap_or_else(|e| {
+        warn!("Cannot read texture {}: {}", file_id, e);
+        None
+    })
+}
+
+/// A temporary address of the file in the storage, if clients should download it from there
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+///
+/// returns: Option<String>: None if the storage does not presign or the file does not exist
+pub async fn presigned_url(file_id: &str) -> Option<String> {
+    if !is_valid_id(file_id) {
         return None;
     }
 
-    Some(file.unwrap())
-}
\ No newline at end of file
+    let storage = texture_storage();
+    let url = storage.presigned_url(file_id).await.unwrap_or_else(|e| {
+        warn!("Cannot presign texture {}: {}", file_id, e);
+        None
+    })?;
+    // Presigning does not reach the storage, a missing file would only fail at the client
+    file_exists(file_id).await.then_some(url)
+}
+
+/// Whether the file is in the texture storage
+///
+/// # Arguments
+///
+/// * `file_id`: The id of the file
+///
+/// returns: bool: false if the file does not exist or the storage can not be reached
+pub async fn file_exists(file_id: &str) -> bool {
+    if !is_valid_id(file_id) {
+        return false;
+    }
+
+    texture_storage().exists(file_id).await.unwrap_or_else(|e| {
+        warn!("Cannot read texture {}: {}", file_id, e);
+        false
+    })
+}
+
+/// Ids are url safe base64 hashes, anything else can not name a stored file
+fn is_valid_id(file_id: &str) -> bool {
+    file_id.len() >= 2
+        && file_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
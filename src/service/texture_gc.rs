@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{ColumnTrait, Condition, DeleteMany, EntityTrait, QueryFilter};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::model::generated::prelude::{Profile, Texture};
+use crate::model::generated::{profile, texture};
+use crate::service::texture::{delete_file, profile_textures};
+use crate::service::texture_storage::{texture_storage, StoredObject};
+use crate::{DATABASE, TEXTURE_CONFIG};
+
+#[derive(Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    /// Number of deleted files
+    pub files: usize,
+    /// Reclaimed space in bytes
+    pub bytes: u64,
+}
+
+/// The grace period from the config
+pub fn default_grace() -> Duration {
+    Duration::from_secs(TEXTURE_CONFIG.gc_grace_hours * 60 * 60)
+}
+
+/// Delete the texture files that no profile uses anymore, with their records
+///
+/// Files written less than `grace` ago are kept, an upload is saved before the profile uses it. Uploads write
+/// the file again when it exists, so a re-uploaded texture is kept as well.
+///
+/// # Arguments
+///
+/// * `grace`: How long an unused file is kept
+///
+/// returns: GcReport
+pub async fn collect_garbage(grace: Duration) -> GcReport {
+    let cutoff = SystemTime::now().checked_sub(grace).unwrap_or(SystemTime::UNIX_EPOCH);
+    let profiles = Profile::find().all(&*DATABASE).await.unwrap();
+    let referenced: HashSet<String> = profile_textures(&profiles).into_iter().collect();
+
+    let mut report = GcReport::default();
+    let files = match texture_storage().list().await {
+        Ok(files) => files,
+        Err(e) => {
+            warn!("Texture GC cannot list the stored files: {}", e);
+            return report;
+        }
+    };
+    for file in select_garbage(files, &referenced, cutoff) {
+        // A profile may have started to use the texture since the snapshot, the record is only deleted while
+        // none does. Files without a record are left by failed uploads and imports, they are deleted as well.
+        let deleted = delete_unused_record(&file.id).exec(&*DATABASE).await;
+        match deleted {
+            Ok(result) if result.rows_affected == 1 => {}
+            Ok(_) => match Texture::find_by_id(&file.id).one(&*DATABASE).await {
+                Ok(None) => {}
+                Ok(Some(_)) => continue,
+                Err(e) => {
+                    warn!("Texture {} kept: {}", file.id, e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                warn!("Texture {} kept: {}", file.id, e);
+                continue;
+            }
+        }
+
+        if let Err(e) = delete_file(&file.id).await {
+            warn!("Texture {} not deleted: {}", file.id, e);
+            continue;
+        }
+        report.files += 1;
+        report.bytes += file.size;
+    }
+    info!("Texture GC deleted {} files, {} bytes reclaimed.", report.files, report.bytes);
+
+    report
+}
+
+/// Delete the record of a texture, unless a profile uses it
+fn delete_unused_record(id: &str) -> DeleteMany<Texture> {
+    let users = Query::select()
+        .expr(Expr::val(1))
+        .from(Profile)
+        .cond_where(
+            Condition::any()
+                .add(
+                    Expr::col((Profile, profile::Column::SkinTexture))
+                        .equals((Texture, texture::Column::Id)),
+                )
+                .add(
+                    Expr::col((Profile, profile::Column::CapeTexture))
+                        .equals((Texture, texture::Column::Id)),
+                ),
+        )
+        .to_owned();
+
+    Texture::delete_many()
+        .filter(texture::Column::Id.eq(id))
+        .filter(Expr::exists(users).not())
+}
+
+/// The files no profile uses, written before the cutoff
+fn select_garbage(files: Vec<StoredObject>, referenced: &HashSet<String>, cutoff: SystemTime) -> Vec<StoredObject> {
+    files
+        .into_iter()
+        .filter(|file| !referenced.contains(&file.id) && file.modified < cutoff)
+        .collect()
+}
+
+#[test]
+fn test_select_garbage() {
+    let now = SystemTime::now();
+    let old = now - Duration::from_secs(48 * 60 * 60);
+    let file = |id: &str, modified: SystemTime| StoredObject {
+        id: id.to_string(),
+        size: 100,
+        modified,
+    };
+    let files = vec![file("used", old), file("unused", old), file("recent", now)];
+    let referenced = HashSet::from(["used".to_string()]);
+
+    let garbage = select_garbage(files, &referenced, now - Duration::from_secs(24 * 60 * 60));
+
+    assert_eq!(garbage.len(), 1);
+    assert_eq!(garbage[0].id, "unused");
+}
+
+#[test]
+fn test_delete_unused_record() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let sql = delete_unused_record("AbCdEf").build(DbBackend::Postgres).to_string();
+
+    assert_eq!(
+        sql,
+        r#"DELETE FROM "texture" WHERE "texture"."id" = 'AbCdEf' AND (NOT EXISTS(SELECT 1 FROM "profile" WHERE "profile"."skin_texture" = "texture"."id" OR "profile"."cape_texture" = "texture"."id"))"#
+    );
+}
@@ -0,0 +1,116 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use crate::service::texture_storage::{StoredObject, TextureStorage};
+
+/// Keep textures on the local disk, as `<dir>/<first two characters of the id>/<id>`
+pub struct LocalTextureStorage {
+    dir: PathBuf,
+}
+
+impl LocalTextureStorage {
+    pub fn new(dir: &str) -> Self {
+        LocalTextureStorage {
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        let prefix = id.get(0..2).ok_or_else(|| format!("Invalid texture id {}", id))?;
+        let mut path = self.dir.clone();
+        path.push(prefix.to_ascii_lowercase());
+        path.push(id);
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl TextureStorage for LocalTextureStorage {
+    async fn put(&self, id: &str, content: Vec<u8>) -> Result<(), String> {
+        let path = self.path(id)?;
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, content).await.map_err(|e| e.to_string())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Vec<u8>>, String> {
+        match tokio::fs::read(self.path(id)?).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.path(id)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn exists(&self, id: &str) -> Result<bool, String> {
+        tokio::fs::try_exists(self.path(id)?).await.map_err(|e| e.to_string())
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>, String> {
+        let mut objects = Vec::new();
+        let mut dirs = match tokio::fs::read_dir(&self.dir).await {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(objects),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        while let Some(dir) = dirs.next_entry().await.map_err(|e| e.to_string())? {
+            if !dir.file_type().await.map_err(|e| e.to_string())?.is_dir() {
+                continue;
+            }
+            let mut entries = tokio::fs::read_dir(dir.path()).await.map_err(|e| e.to_string())?;
+            while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+                let metadata = entry.metadata().await.map_err(|e| e.to_string())?;
+                let id = entry.file_name().to_string_lossy().to_string();
+                // Other files can not be read through the storage
+                if !metadata.is_file() || self.path(&id).ok() != Some(entry.path()) {
+                    continue;
+                }
+                objects.push(StoredObject {
+                    id,
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                });
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+#[tokio::test]
+async fn test_local_texture_storage() {
+    let dir = std::env::temp_dir().join(format!("texture-storage-test-{}", std::process::id()));
+    let storage = LocalTextureStorage::new(dir.to_str().unwrap());
+
+    assert!(!storage.exists("AbCdEf").await.unwrap());
+    assert_eq!(storage.get("AbCdEf").await.unwrap(), None);
+
+    storage.put("AbCdEf", b"content".to_vec()).await.unwrap();
+    assert!(dir.join("ab").join("AbCdEf").is_file());
+    assert!(storage.exists("AbCdEf").await.unwrap());
+    assert_eq!(storage.get("AbCdEf").await.unwrap(), Some(b"content".to_vec()));
+
+    std::fs::write(dir.join("ab").join("a"), b"stray").unwrap();
+    assert!(storage.get("a").await.is_err());
+    let objects = storage.list().await.unwrap();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].id, "AbCdEf");
+    assert_eq!(objects[0].size, 7);
+
+    storage.delete("AbCdEf").await.unwrap();
+    storage.delete("AbCdEf").await.unwrap();
+    assert!(!storage.exists("AbCdEf").await.unwrap());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,59 @@
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+
+use crate::config::texture::TextureStorageType;
+use crate::service::texture_storage::local::LocalTextureStorage;
+use crate::service::texture_storage::s3::S3TextureStorage;
+use crate::TEXTURE_CONFIG;
+
+pub mod local;
+pub mod s3;
+
+lazy_static! {
+    static ref TEXTURE_STORAGE: Box<dyn TextureStorage> = match TEXTURE_CONFIG.storage {
+        TextureStorageType::Local => Box::new(LocalTextureStorage::new(&TEXTURE_CONFIG.local_dir)),
+        TextureStorageType::S3 => Box::new(S3TextureStorage::new(&TEXTURE_CONFIG.s3)),
+    };
+}
+
+/// A file kept by a storage
+#[derive(Clone, Debug)]
+pub struct StoredObject {
+    pub id: String,
+    /// Size in bytes
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Where texture files are kept, by id
+///
+/// Files are never changed once written, the id is a hash of the content.
+#[async_trait]
+pub trait TextureStorage: Send + Sync {
+    async fn put(&self, id: &str, content: Vec<u8>) -> Result<(), String>;
+
+    /// Read a file, None if it does not exist
+    async fn get(&self, id: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Remove a file, does nothing if it does not exist
+    async fn delete(&self, id: &str) -> Result<(), String>;
+
+    async fn exists(&self, id: &str) -> Result<bool, String>;
+
+    /// List every file of the storage
+    async fn list(&self) -> Result<Vec<StoredObject>, String>;
+
+    /// A temporary address to download the file from the storage itself
+    ///
+    /// returns: None if the storage can not be reached by clients or presigning is disabled
+    async fn presigned_url(&self, _id: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+}
+
+/// The storage chosen by `storage` in the texture config
+pub fn texture_storage() -> &'static dyn TextureStorage {
+    TEXTURE_STORAGE.as_ref()
+}
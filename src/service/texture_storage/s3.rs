@@ -0,0 +1,181 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+
+use crate::config::texture::S3Config;
+use crate::service::texture_storage::{StoredObject, TextureStorage};
+
+/// Keep textures in a bucket of an S3 compatible object storage, as `<prefix><id>`
+pub struct S3TextureStorage {
+    client: Client,
+    bucket: String,
+    prefix: String,
+    /// None if downloads are not redirected to the storage
+    presign_expiry: Option<Duration>,
+}
+
+impl S3TextureStorage {
+    pub fn new(config: &S3Config) -> Self {
+        let credentials = Credentials::new(&config.access_key, &config.secret_key, None, None, "config");
+        let mut builder = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.clone()))
+            .credentials_provider(credentials)
+            .force_path_style(config.path_style);
+        if !config.endpoint.is_empty() {
+            builder = builder.endpoint_url(&config.endpoint);
+        }
+
+        S3TextureStorage {
+            client: Client::from_conf(builder.build()),
+            bucket: config.bucket.clone(),
+            prefix: config.prefix.clone(),
+            presign_expiry: config
+                .presign
+                .then(|| Duration::from_secs(config.presign_seconds)),
+        }
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+}
+
+#[async_trait]
+impl TextureStorage for S3TextureStorage {
+    async fn put(&self, id: &str, content: Vec<u8>) -> Result<(), String> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.key(id))
+            .content_type("image/png")
+            .body(ByteStream::from(content))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Vec<u8>>, String> {
+        let output = match self.client.get_object().bucket(&self.bucket).key(self.key(id)).send().await {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        let content = output.body.collect().await.map_err(|e| e.to_string())?;
+
+        Ok(Some(content.into_bytes().to_vec()))
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        // Deleting a missing key succeeds
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.key(id))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn exists(&self, id: &str) -> Result<bool, String> {
+        match self.client.head_object().bucket(&self.bucket).key(self.key(id)).send().await {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>, String> {
+        let mut objects = Vec::new();
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(&self.prefix)
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| e.to_string())?;
+            for object in page.contents() {
+                let Some(id) = object.key().and_then(|key| key.strip_prefix(&self.prefix)) else {
+                    continue;
+                };
+                objects.push(StoredObject {
+                    id: id.to_string(),
+                    size: object.size().unwrap_or_default() as u64,
+                    modified: object
+                        .last_modified()
+                        .and_then(|time| SystemTime::try_from(*time).ok())
+                        .unwrap_or_else(SystemTime::now),
+                });
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn presigned_url(&self, id: &str) -> Result<Option<String>, String> {
+        let Some(expiry) = self.presign_expiry else {
+            return Ok(None);
+        };
+        let presigning_config = PresigningConfig::expires_in(expiry).map_err(|e| e.to_string())?;
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.key(id))
+            .presigned(presigning_config)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(Some(request.uri().to_string()))
+    }
+}
+
+/// Round trip through a real storage, only run when `TEST_S3_ENDPOINT` is set
+///
+/// A local MinIO works: `minio server /tmp/minio`, then create the `textures` bucket and set
+/// `TEST_S3_ENDPOINT=http://127.0.0.1:9000` with `TEST_S3_ACCESS_KEY` and `TEST_S3_SECRET_KEY`.
+#[tokio::test]
+async fn test_s3_texture_storage() {
+    let Ok(endpoint) = std::env::var("TEST_S3_ENDPOINT") else {
+        return;
+    };
+    let config = S3Config {
+        endpoint,
+        region: String::from("us-east-1"),
+        bucket: std::env::var("TEST_S3_BUCKET").unwrap_or_else(|_| String::from("textures")),
+        prefix: format!("test-{}/", std::process::id()),
+        access_key: std::env::var("TEST_S3_ACCESS_KEY").unwrap_or_default(),
+        secret_key: std::env::var("TEST_S3_SECRET_KEY").unwrap_or_default(),
+        path_style: true,
+        presign: true,
+        presign_seconds: 60,
+    };
+    let storage = S3TextureStorage::new(&config);
+
+    assert!(!storage.exists("AbCdEf").await.unwrap());
+    assert_eq!(storage.get("AbCdEf").await.unwrap(), None);
+
+    storage.put("AbCdEf", b"content".to_vec()).await.unwrap();
+    assert!(storage.exists("AbCdEf").await.unwrap());
+    assert_eq!(storage.get("AbCdEf").await.unwrap(), Some(b"content".to_vec()));
+    let objects = storage.list().await.unwrap();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].id, "AbCdEf");
+    assert_eq!(objects[0].size, 7);
+    let url = storage.presigned_url("AbCdEf").await.unwrap().unwrap();
+    assert!(url.contains("X-Amz-Signature"));
+
+    storage.delete("AbCdEf").await.unwrap();
+    assert!(!storage.exists("AbCdEf").await.unwrap());
+}
@@ -1,22 +1,31 @@
-use std::sync::Arc;
-
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
-use moka::future::Cache;
+use tracing::debug;
 
+use crate::config::auth::TokenStoreType;
 use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::token_store::database::DatabaseTokenStore;
+use crate::service::token_store::file::FileTokenStore;
+use crate::service::token_store::memory::MemoryTokenStore;
+use crate::service::token_store::TokenStore;
 use crate::AUTH_CONFIG;
 
 lazy_static! {
-    static ref TOKEN_CACHE: Cache<String, TokenInfo> = Cache::builder()
-        .time_to_live(chrono::Duration::days(AUTH_CONFIG.token_keep_days).to_std().unwrap()) //available for 14 days
-        .build();
+    static ref TOKEN_STORE: Box<dyn TokenStore> = {
+        let time_to_live = chrono::Duration::days(AUTH_CONFIG.token_keep_days).to_std().unwrap(); //available for 14 days
+        match AUTH_CONFIG.token_store {
+            TokenStoreType::Memory => Box::new(MemoryTokenStore::new(time_to_live)),
+            TokenStoreType::Database => Box::new(DatabaseTokenStore::new(time_to_live)),
+            TokenStoreType::File => Box::new(FileTokenStore::new(&AUTH_CONFIG.token_store_file)),
+        }
+    };
 }
 
 #[derive(Clone)]
 pub struct TokenInfo {
     pub client_token: String,
     pub user_id: String,
+    pub profile_id: Option<String>,
     pub issued_time: DateTime<Utc>,
     pub available: bool,
 }
@@ -32,16 +41,34 @@ fn new_token() -> String {
     UuidNoChar::new().to_string()
 }
 
-pub async fn sign_new_token(user_id: String, client_token: Option<String>) -> (String, String) {
+fn keep_deadline() -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::days(AUTH_CONFIG.token_keep_days)
+}
+
+/// Sign a new access token for the user
+///
+/// # Arguments
+///
+/// * `user_id`: The owner of the token
+/// * `client_token`: The client token sent by the launcher, a new one is generated if None
+/// * `profile_id`: The profile bound to the token, None if the user has not selected one yet
+///
+/// returns: (String, String): (access_token, client_token)
+pub async fn sign_new_token(
+    user_id: String,
+    client_token: Option<String>,
+    profile_id: Option<String>,
+) -> (String, String) {
     let access_token = new_token();
     let client_token = client_token.unwrap_or_else(new_token);
     let token_info = TokenInfo {
         client_token: client_token.clone(),
         user_id: user_id.clone(),
+        profile_id,
         issued_time: Utc::now(),
         available: true,
     };
-    TOKEN_CACHE.insert(access_token.clone(), token_info).await;
+    TOKEN_STORE.issue(&access_token, token_info).await;
 
     invalidate_tokens(&user_id, AUTH_CONFIG.max_token_allowed).await;
 
@@ -49,7 +76,13 @@ pub async fn sign_new_token(user_id: String, client_token: Option<String>) -> (S
 }
 
 pub async fn get_token_info(access_token: &str) -> Option<TokenInfo> {
-    TOKEN_CACHE.get(access_token).await
+    let token_info = TOKEN_STORE.lookup(access_token).await?;
+    if token_info.issued_time <= keep_deadline() {
+        TOKEN_STORE.revoke(access_token).await;
+        return None;
+    }
+
+    Some(token_info)
 }
 
 pub async fn check_token_state(access_token: &str, client_token: Option<String>) -> TokenState {
@@ -74,24 +107,50 @@ pub async fn check_token_state(access_token: &str, client_token: Option<String>)
 }
 
 pub async fn invalidate_token(token: &str) {
-    TOKEN_CACHE.invalidate(token).await;
+    TOKEN_STORE.revoke(token).await;
 }
 
 pub async fn invalidate_tokens(user_id: &str, keep_alive: u32) {
-    let mut tokens = TOKEN_CACHE
-        .iter()
-        .filter(|(_, token_info)| token_info.user_id == user_id)
-        .collect::<Vec<(Arc<String>, TokenInfo)>>();
+    if keep_alive == 0 {
+        TOKEN_STORE.revoke_by_user(user_id).await;
+        return;
+    }
+
+    let mut tokens = TOKEN_STORE.list_by_user(user_id).await;
     if tokens.len() <= keep_alive as usize {
         return;
     }
 
     tokens.sort_by_key(|token| token.1.issued_time);
-    let tokens = tokens
-        .iter()
-        .map(|(token, _)| token.clone().to_string())
-        .collect::<Vec<String>>();
-    for token in &tokens[..tokens.len() - keep_alive as usize] {
-        TOKEN_CACHE.invalidate(token).await;
+    for (token, _) in &tokens[..tokens.len() - keep_alive as usize] {
+        TOKEN_STORE.revoke(token).await;
     }
 }
+
+/// Revoke every token of the user except the given one
+pub async fn invalidate_other_tokens(user_id: &str, access_token: &str) {
+    for (token, _) in TOKEN_STORE.list_by_user(user_id).await {
+        if token != access_token {
+            TOKEN_STORE.revoke(&token).await;
+        }
+    }
+}
+
+/// Revoke every token of the user that is bound to the profile
+pub async fn invalidate_profile_tokens(user_id: &str, profile_id: &str) {
+    for (token, token_info) in TOKEN_STORE.list_by_user(user_id).await {
+        if token_info.profile_id.as_deref() == Some(profile_id) {
+            TOKEN_STORE.revoke(&token).await;
+        }
+    }
+}
+
+/// Remove tokens that are older than `token_keep_days` from the token store
+///
+/// returns: u64: the number of tokens removed
+pub async fn purge_expired_tokens() -> u64 {
+    let removed = TOKEN_STORE.purge(keep_deadline()).await;
+    debug!("Purged {} expired tokens.", removed);
+
+    removed
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::model::generated::prelude::Token;
+use crate::model::generated::token;
+use crate::service::token::TokenInfo;
+use crate::service::token_store::TokenStore;
+use crate::DATABASE;
+
+/// How long a token stays cached, tokens revoked by the CLI or another server are rejected after it at the latest
+const CACHE_TIME: Duration = Duration::from_secs(10);
+
+/// Store backed by the `token` table, with a moka cache as read-through layer
+pub struct DatabaseTokenStore {
+    cache: Cache<String, TokenInfo>,
+}
+
+impl DatabaseTokenStore {
+    pub fn new(time_to_live: Duration) -> Self {
+        DatabaseTokenStore {
+            cache: Cache::builder().time_to_live(time_to_live.min(CACHE_TIME)).build(),
+        }
+    }
+}
+
+impl From<token::Model> for TokenInfo {
+    fn from(value: token::Model) -> Self {
+        TokenInfo {
+            client_token: value.client_token,
+            user_id: value.user_id,
+            profile_id: value.profile_id,
+            issued_time: value.issued_time.and_utc(),
+            available: value.available,
+        }
+    }
+}
+
+#[async_trait]
+impl TokenStore for DatabaseTokenStore {
+    async fn issue(&self, access_token: &str, token_info: TokenInfo) {
+        token::ActiveModel {
+            access_token: Set(access_token.to_string()),
+            client_token: Set(token_info.client_token.clone()),
+            user_id: Set(token_info.user_id.clone()),
+            issued_time: Set(token_info.issued_time.naive_utc()),
+            available: Set(token_info.available),
+            profile_id: Set(token_info.profile_id.clone()),
+        }
+        .insert(&*DATABASE)
+        .await
+        .unwrap();
+        self.cache.insert(access_token.to_string(), token_info).await;
+    }
+
+    async fn lookup(&self, access_token: &str) -> Option<TokenInfo> {
+        if let Some(token_info) = self.cache.get(access_token).await {
+            return Some(token_info);
+        }
+
+        let token_info: TokenInfo = Token::find_by_id(access_token)
+            .one(&*DATABASE)
+            .await
+            .unwrap()?
+            .into();
+        self.cache
+            .insert(access_token.to_string(), token_info.clone())
+            .await;
+
+        Some(token_info)
+    }
+
+    async fn revoke(&self, access_token: &str) {
+        Token::delete_by_id(access_token)
+            .exec(&*DATABASE)
+            .await
+            .unwrap();
+        self.cache.invalidate(access_token).await;
+    }
+
+    async fn revoke_by_user(&self, user_id: &str) {
+        for (access_token, _) in self.list_by_user(user_id).await {
+            self.revoke(&access_token).await;
+        }
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Vec<(String, TokenInfo)> {
+        Token::find()
+            .filter(token::Column::UserId.eq(user_id))
+            .all(&*DATABASE)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|token| (token.access_token.clone(), token.into()))
+            .collect()
+    }
+
+    async fn purge(&self, issued_before: DateTime<Utc>) -> u64 {
+        let result = Token::delete_many()
+            .filter(token::Column::IssuedTime.lte(issued_before.naive_utc()))
+            .exec(&*DATABASE)
+            .await
+            .unwrap();
+        // Cached entries are dropped lazily by `service::token` when they are looked up
+
+        result.rows_affected
+    }
+}
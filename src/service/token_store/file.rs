@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::service::token::TokenInfo;
+use crate::service::token_store::TokenStore;
+
+/// Store keeping every token in a json file, rewritten on every change
+pub struct FileTokenStore {
+    path: PathBuf,
+    tokens: Mutex<HashMap<String, StoredToken>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct StoredToken {
+    client_token: String,
+    user_id: String,
+    #[serde(default)]
+    profile_id: Option<String>,
+    issued_time: i64,
+    available: bool,
+}
+
+impl From<TokenInfo> for StoredToken {
+    fn from(value: TokenInfo) -> Self {
+        StoredToken {
+            client_token: value.client_token,
+            user_id: value.user_id,
+            profile_id: value.profile_id,
+            issued_time: value.issued_time.timestamp_millis(),
+            available: value.available,
+        }
+    }
+}
+
+impl From<StoredToken> for TokenInfo {
+    fn from(value: StoredToken) -> Self {
+        TokenInfo {
+            client_token: value.client_token,
+            user_id: value.user_id,
+            profile_id: value.profile_id,
+            issued_time: DateTime::from_timestamp_millis(value.issued_time).unwrap_or_default(),
+            available: value.available,
+        }
+    }
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let tokens = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .unwrap_or_else(|e| panic!("Cannot parse token file {}: {}", path.display(), e)),
+            Err(_) => HashMap::new(),
+        };
+
+        FileTokenStore {
+            path,
+            tokens: Mutex::new(tokens),
+        }
+    }
+
+    async fn save(&self, tokens: &HashMap<String, StoredToken>) {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await.unwrap();
+        }
+        // Write to a temporary file first so a crash never leaves a half written store
+        let temp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&temp_path, serde_json::to_vec(tokens).unwrap())
+            .await
+            .unwrap();
+        tokio::fs::rename(&temp_path, &self.path).await.unwrap();
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn issue(&self, access_token: &str, token_info: TokenInfo) {
+        let mut tokens = self.tokens.lock().await;
+        tokens.insert(access_token.to_string(), token_info.into());
+        self.save(&tokens).await;
+    }
+
+    async fn lookup(&self, access_token: &str) -> Option<TokenInfo> {
+        let tokens = self.tokens.lock().await;
+        tokens.get(access_token).cloned().map(TokenInfo::from)
+    }
+
+    async fn revoke(&self, access_token: &str) {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.remove(access_token).is_some() {
+            self.save(&tokens).await;
+        }
+    }
+
+    async fn revoke_by_user(&self, user_id: &str) {
+        let mut tokens = self.tokens.lock().await;
+        let count = tokens.len();
+        tokens.retain(|_, token| token.user_id != user_id);
+        if tokens.len() != count {
+            self.save(&tokens).await;
+        }
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Vec<(String, TokenInfo)> {
+        let tokens = self.tokens.lock().await;
+        tokens
+            .iter()
+            .filter(|(_, token)| token.user_id == user_id)
+            .map(|(access_token, token)| (access_token.clone(), token.clone().into()))
+            .collect()
+    }
+
+    async fn purge(&self, issued_before: DateTime<Utc>) -> u64 {
+        let mut tokens = self.tokens.lock().await;
+        let count = tokens.len();
+        tokens.retain(|_, token| token.issued_time > issued_before.timestamp_millis());
+        let removed = (count - tokens.len()) as u64;
+        if removed > 0 {
+            self.save(&tokens).await;
+        }
+
+        removed
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+
+use crate::service::token::TokenInfo;
+use crate::service::token_store::TokenStore;
+
+/// Process-local store, every token is lost on restart
+pub struct MemoryTokenStore {
+    cache: Cache<String, TokenInfo>,
+}
+
+impl MemoryTokenStore {
+    pub fn new(time_to_live: Duration) -> Self {
+        MemoryTokenStore {
+            cache: Cache::builder().time_to_live(time_to_live).build(),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn issue(&self, access_token: &str, token_info: TokenInfo) {
+        self.cache.insert(access_token.to_string(), token_info).await;
+    }
+
+    async fn lookup(&self, access_token: &str) -> Option<TokenInfo> {
+        self.cache.get(access_token).await
+    }
+
+    async fn revoke(&self, access_token: &str) {
+        self.cache.invalidate(access_token).await;
+    }
+
+    async fn revoke_by_user(&self, user_id: &str) {
+        for (access_token, _) in self.list_by_user(user_id).await {
+            self.cache.invalidate(&access_token).await;
+        }
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Vec<(String, TokenInfo)> {
+        self.cache
+            .iter()
+            .filter(|(_, token_info)| token_info.user_id == user_id)
+            .map(|(access_token, token_info)| (access_token.to_string(), token_info))
+            .collect()
+    }
+
+    async fn purge(&self, issued_before: DateTime<Utc>) -> u64 {
+        let expired = self
+            .cache
+            .iter()
+            .filter(|(_, token_info)| token_info.issued_time <= issued_before)
+            .map(|(access_token, _)| access_token)
+            .collect::<Vec<_>>();
+        for access_token in &expired {
+            self.cache.invalidate(access_token.as_str()).await;
+        }
+
+        expired.len() as u64
+    }
+}
+
+#[tokio::test]
+async fn test_memory_token_store() {
+    let store = MemoryTokenStore::new(Duration::from_secs(60));
+    let token_info = |user_id: &str| TokenInfo {
+        client_token: "client".to_string(),
+        user_id: user_id.to_string(),
+        profile_id: None,
+        issued_time: Utc::now(),
+        available: true,
+    };
+    store.issue("a", token_info("alice")).await;
+    store.issue("b", token_info("alice")).await;
+    store.issue("c", token_info("bob")).await;
+
+    assert!(store.lookup("a").await.is_some());
+    assert_eq!(store.list_by_user("alice").await.len(), 2);
+
+    store.revoke("a").await;
+    assert!(store.lookup("a").await.is_none());
+
+    store.revoke_by_user("alice").await;
+    assert!(store.list_by_user("alice").await.is_empty());
+    assert!(store.lookup("c").await.is_some());
+}
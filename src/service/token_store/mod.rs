@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::service::token::TokenInfo;
+
+pub mod database;
+pub mod file;
+pub mod memory;
+
+/// Storage backend of access tokens
+///
+/// Expiry policy is applied by `service::token`, stores only keep what they are given.
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Save a newly signed token
+    async fn issue(&self, access_token: &str, token_info: TokenInfo);
+
+    /// Find a token by its access token
+    async fn lookup(&self, access_token: &str) -> Option<TokenInfo>;
+
+    /// Remove a single token, does nothing if it does not exist
+    async fn revoke(&self, access_token: &str);
+
+    /// Remove every token owned by the user
+    async fn revoke_by_user(&self, user_id: &str);
+
+    /// List every token owned by the user as `(access_token, token_info)`
+    async fn list_by_user(&self, user_id: &str) -> Vec<(String, TokenInfo)>;
+
+    /// Remove tokens issued before the given time
+    ///
+    /// returns: u64: the number of tokens removed
+    async fn purge(&self, issued_before: DateTime<Utc>) -> u64;
+}
@@ -0,0 +1,302 @@
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, IntoActiveModel,
+    ModelTrait, NotSet, QueryFilter, QueryOrder, SqlErr,
+};
+
+use crate::model::generated::prelude::{Profile, User};
+use crate::model::generated::{profile, user};
+use crate::model::serialized::uuid::UuidNoChar;
+use crate::service::password::generate_password_hash;
+use crate::service::texture::{profile_textures, update_ref_counts};
+use crate::service::token::{invalidate_other_tokens, invalidate_tokens};
+use crate::{AUTH_CONFIG, DATABASE};
+
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// A loose check of the email format, the address is not required to exist
+pub fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+
+    email.len() <= 254
+        && !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+pub fn is_valid_password(password: &str) -> bool {
+    password.chars().count() >= AUTH_CONFIG.password_min_length && password.len() <= MAX_PASSWORD_LENGTH
+}
+
+/// Usernames follow the rules of profile names, so they can never be mistaken for an email
+pub fn is_valid_username(username: &str) -> bool {
+    username.len() >= 3 && crate::service::profile::is_valid_name(username)
+}
+
+/// Check whether a user has the email, ignoring case
+///
+/// The connection can be a transaction, which also sees the users it inserted.
+pub async fn is_email_taken<C: ConnectionTrait>(db: &C, email: &str) -> bool {
+    User::find().filter(email_is(email)).one(db).await.unwrap().is_some()
+}
+
+/// Find a user by its email, ignoring case as the unique index does
+pub async fn find_user_by_email(email: &str) -> Option<user::Model> {
+    User::find().filter(email_is(email)).one(&*DATABASE).await.unwrap()
+}
+
+fn email_is(email: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(user::Column::Email))).eq(email.to_lowercase())
+}
+
+pub async fn is_username_taken(username: &str) -> bool {
+    User::find()
+        .filter(Expr::expr(Func::lower(Expr::col(user::Column::Username))).eq(username.to_lowercase()))
+        .one(&*DATABASE)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+/// A unique value another user or profile already took
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    Email,
+    Username,
+    ProfileName,
+}
+
+/// Which unique value a failed write conflicted on
+///
+/// The checks done before a write can not see a concurrent one, the unique indexes settle it.
+/// The violated index is only named in the message of the database.
+pub fn unique_conflict(error: &DbErr) -> Option<Conflict> {
+    match error.sql_err()? {
+        SqlErr::UniqueConstraintViolation(message) => conflict_of(DATABASE.get_database_backend(), &message),
+        _ => None,
+    }
+}
+
+fn conflict_of(backend: DbBackend, message: &str) -> Option<Conflict> {
+    match (backend, violated_index(backend, message)?) {
+        (_, "idx_user_email_lower")
+        | (DbBackend::Postgres, "user_email_key")
+        | (DbBackend::MySql, "email")
+        | (DbBackend::Sqlite, "user.email") => Some(Conflict::Email),
+        (_, "idx_user_username_lower") => Some(Conflict::Username),
+        (_, "idx_profile_name_lower") => Some(Conflict::ProfileName),
+        _ => None,
+    }
+}
+
+/// The name of the violated index, or `table.column` for a unique column on SQLite
+fn violated_index(backend: DbBackend, message: &str) -> Option<&str> {
+    match backend {
+        DbBackend::Postgres => message
+            .strip_prefix("duplicate key value violates unique constraint \"")?
+            .strip_suffix('"'),
+        DbBackend::MySql => {
+            // The duplicated value comes first and can contain anything, the key is the last quoted part
+            const KEY: &str = "' for key '";
+            let message = message.strip_prefix("Duplicate entry '")?.strip_suffix('\'')?;
+            let key = &message[message.rfind(KEY)? + KEY.len()..];
+            // MySQL 8 names the key with its table
+            Some(key.split_once('.').map_or(key, |(_, key)| key))
+        }
+        DbBackend::Sqlite => {
+            let failed = message.strip_prefix("UNIQUE constraint failed: ")?;
+            match failed.strip_prefix("index '") {
+                Some(index) => index.strip_suffix('\''),
+                None => Some(failed),
+            }
+        }
+    }
+}
+
+pub struct NewUser {
+    pub email: String,
+    pub password: String,
+    pub profile_name: String,
+    pub username: Option<String>,
+    pub preferred_language: Option<String>,
+    pub invite_code: Option<String>,
+    pub verified: bool,
+}
+
+/// Create a user together with its first profile
+///
+/// # Arguments
+///
+/// * `db`: The connection to use, can be a transaction
+/// * `new_user`: The user to create, nothing is validated here
+///
+/// returns: Result<(user::Model, profile::Model), DbErr>, see `unique_conflict` for taken values
+pub async fn create_user<C: ConnectionTrait>(
+    db: &C,
+    new_user: NewUser,
+) -> Result<(user::Model, profile::Model), DbErr> {
+    // The user and the profile reference each other, so the profile is selected once both exist
+    let user = user::ActiveModel {
+        id: Set(UuidNoChar::new().to_string()),
+        username: Set(new_user.username),
+        email: Set(new_user.email),
+        password: Set(generate_password_hash(&new_user.password)),
+        profile_id: Set(None),
+        preferred_language: Set(new_user.preferred_language),
+        create_time: NotSet,
+        update_time: NotSet,
+        invite_code: Set(new_user.invite_code),
+        verified: Set(new_user.verified),
+    }.insert(db).await?;
+
+    let profile = profile::ActiveModel {
+        id: Set(UuidNoChar::new().to_string()),
+        name: Set(new_user.profile_name),
+        model: NotSet,
+        owner_id: Set(user.id.clone()),
+        skin_texture: NotSet,
+        cape_texture: NotSet,
+        create_time: NotSet,
+        update_time: NotSet,
+    }.insert(db).await?;
+
+    let mut user = user.into_active_model();
+    user.profile_id = Set(Some(profile.id.clone()));
+    let user = user.update(db).await?;
+
+    Ok((user, profile))
+}
+
+/// Find a user by its id, email or username
+pub async fn find_user(identifier: &str) -> Option<user::Model> {
+    if identifier.contains('@') {
+        return find_user_by_email(identifier).await;
+    }
+    let column = if identifier.len() == 32 && identifier.chars().all(|c| c.is_ascii_hexdigit()) {
+        user::Column::Id
+    } else {
+        user::Column::Username
+    };
+
+    User::find()
+        .filter(column.eq(identifier))
+        .one(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+pub async fn list_users() -> Vec<user::Model> {
+    User::find()
+        .order_by_asc(user::Column::CreateTime)
+        .all(&*DATABASE)
+        .await
+        .unwrap()
+}
+
+/// Delete a user with all its profiles and tokens
+pub async fn delete_user(user: user::Model) {
+    invalidate_tokens(&user.id, 0).await;
+    let profiles = user.find_related(Profile).all(&*DATABASE).await.unwrap();
+    Profile::delete_many()
+        .filter(profile::Column::OwnerId.eq(&user.id))
+        .exec(&*DATABASE)
+        .await
+        .unwrap();
+    User::delete_by_id(&user.id)
+        .exec(&*DATABASE)
+        .await
+        .unwrap();
+    update_ref_counts(&*DATABASE, profile_textures(&profiles)).await;
+}
+
+/// Replace the password of the user and revoke every other session
+///
+/// # Arguments
+///
+/// * `user`: The user changing its password
+/// * `password`: The new password, not validated here
+/// * `access_token`: The token used for the change, it stays valid
+///
+/// returns: user::Model
+pub async fn change_password(user: user::Model, password: &str, access_token: &str) -> user::Model {
+    let mut user = user.into_active_model();
+    user.password = Set(generate_password_hash(password));
+    user.update_time = Set(chrono::Utc::now().naive_utc());
+    let user = user.update(&*DATABASE).await.unwrap();
+
+    invalidate_other_tokens(&user.id, access_token).await;
+    user
+}
+
+/// Replace the email of the user, which has to be verified again if `reverify_changed_email` is set
+///
+/// returns: Result<user::Model, DbErr>, see `unique_conflict` if the email was taken meanwhile
+pub async fn change_email(user: user::Model, email: &str) -> Result<user::Model, DbErr> {
+    let changed = !user.email.eq_ignore_ascii_case(email);
+    let mut user = user.into_active_model();
+    user.email = Set(email.to_string());
+    if changed && AUTH_CONFIG.reverify_changed_email {
+        user.verified = Set(false);
+    }
+    user.update_time = Set(chrono::Utc::now().naive_utc());
+    user.update(&*DATABASE).await
+}
+
+/// Store the password again with the configured algorithm, tokens are kept
+pub async fn rehash_password(user: user::Model, password: &str) -> user::Model {
+    let mut user = user.into_active_model();
+    user.password = Set(generate_password_hash(password));
+    user.update(&*DATABASE).await.unwrap()
+}
+
+/// Replace the password of the user and revoke all of its tokens
+pub async fn reset_password(user: user::Model, password: &str) {
+    let user_id = user.id.clone();
+    let mut user = user.into_active_model();
+    user.password = Set(generate_password_hash(password));
+    user.update_time = Set(chrono::Utc::now().naive_utc());
+    user.update(&*DATABASE).await.unwrap();
+
+    invalidate_tokens(&user_id, 0).await;
+}
+
+#[test]
+fn test_conflict_of() {
+    use sea_orm::DatabaseBackend::{MySql, Postgres, Sqlite};
+
+    let postgres = |index: &str| {
+        conflict_of(Postgres, &format!(r#"duplicate key value violates unique constraint "{}""#, index))
+    };
+    assert_eq!(postgres("user_email_key"), Some(Conflict::Email));
+    assert_eq!(postgres("idx_user_email_lower"), Some(Conflict::Email));
+    assert_eq!(postgres("idx_user_username_lower"), Some(Conflict::Username));
+    assert_eq!(postgres("idx_profile_name_lower"), Some(Conflict::ProfileName));
+    assert_eq!(postgres("token_pkey"), None);
+
+    // The duplicated value is part of the message
+    assert_eq!(
+        conflict_of(MySql, "Duplicate entry 'steve' for key 'user.idx_user_username_lower'"),
+        Some(Conflict::Username)
+    );
+    assert_eq!(conflict_of(MySql, "Duplicate entry 'a@b.c' for key 'email'"), Some(Conflict::Email));
+    assert_eq!(
+        conflict_of(MySql, "Duplicate entry 'username' for key 'profile.idx_profile_name_lower'"),
+        Some(Conflict::ProfileName)
+    );
+    assert_eq!(conflict_of(MySql, "Duplicate entry 'email' for key 'token.PRIMARY'"), None);
+    assert_eq!(conflict_of(MySql, "Duplicate entry 'x' for key 'email' for key 'user.PRIMARY'"), None);
+
+    assert_eq!(
+        conflict_of(Sqlite, "UNIQUE constraint failed: index 'idx_profile_name_lower'"),
+        Some(Conflict::ProfileName)
+    );
+    assert_eq!(conflict_of(Sqlite, "UNIQUE constraint failed: user.email"), Some(Conflict::Email));
+    assert_eq!(conflict_of(Sqlite, "UNIQUE constraint failed: token.access_token"), None);
+    assert_eq!(conflict_of(Postgres, "UNIQUE constraint failed: user.email"), None);
+}
@@ -0,0 +1,59 @@
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel};
+
+use crate::model::generated::prelude::User;
+use crate::model::generated::user;
+use crate::service::mail::{send_mail, Mail};
+use crate::service::signed_token::{parse_token, sign_token};
+use crate::{AUTH_CONFIG, CORE_CONFIG, DATABASE, META_CONFIG};
+
+const PURPOSE: &str = "verify_email";
+
+/// Mail a verification link to the current email of the user
+pub async fn send_verification_mail(user: &user::Model) -> Result<(), String> {
+    let token = sign_token(
+        PURPOSE,
+        &user.id,
+        &user.email.to_lowercase(),
+        chrono::Duration::hours(AUTH_CONFIG.verification_token_hours),
+    );
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: format!("Verify your email for {}", META_CONFIG.server_name),
+        body: format!(
+            "Open the link below to verify your email, it expires in {} hours.\n\n{}/api/user/verify?token={}\n",
+            AUTH_CONFIG.verification_token_hours, CORE_CONFIG.base_url, token
+        ),
+    };
+
+    send_mail(&mail).await
+}
+
+/// Mark the user verified if the token is valid
+///
+/// The token is bound to the email it was sent to, it stops working once the email is changed.
+///
+/// returns: Option<user::Model>: None if the token is invalid or expired
+pub async fn verify_email(token: &str) -> Option<user::Model> {
+    let token = parse_token(token)?;
+    let user = User::find_by_id(&token.user_id)
+        .one(&*DATABASE)
+        .await
+        .unwrap()?;
+    if !token.verify(PURPOSE, &user.email.to_lowercase()) {
+        return None;
+    }
+
+    Some(set_verified(user, true).await)
+}
+
+pub async fn set_verified(user: user::Model, verified: bool) -> user::Model {
+    if user.verified == verified {
+        return user;
+    }
+
+    let mut user = user.into_active_model();
+    user.verified = Set(verified);
+    user.update_time = Set(chrono::Utc::now().naive_utc());
+    user.update(&*DATABASE).await.unwrap()
+}